@@ -11,8 +11,10 @@ pub(crate) enum Cli {
 
 #[derive(clap::Args, Debug)]
 #[command(version, about, long_about = None)]
+#[allow(clippy::struct_excessive_bools)]
 pub(crate) struct WhichpArgs {
-    pub(crate) program: OsString,
+    #[arg(required_unless_present = "load")]
+    pub(crate) program: Option<OsString>,
 
     #[arg(short, long)]
     pub(crate) cwd: Option<PathBuf>,
@@ -20,8 +22,117 @@ pub(crate) struct WhichpArgs {
     #[arg(short, long)]
     pub(crate) path: Option<OsString>,
 
+    /// Diagnose with the environment captured in a file by `env -0` or `env`,
+    /// the PATH comes from the file unless `--path` is given
+    #[arg(long, value_name = "FILE")]
+    pub(crate) env_file: Option<PathBuf>,
+
     #[arg(short, long)]
     pub(crate) suggest: Option<usize>,
+
+    /// Also diagnose with the PATH used by sudo, read from `secure_path` in /etc/sudoers
+    #[arg(long)]
+    pub(crate) sudo: bool,
+
+    /// Also diagnose with this PATH as the `secure_path` used by sudo
+    #[arg(long)]
+    pub(crate) secure_path: Option<OsString>,
+
+    /// Exit with a non-zero status if any problem is detected, even when the program is found.
+    /// Includes warnings such as shadowed executables, invalid or relative PATH parts,
+    /// and duplicate PATH directories
+    #[arg(long)]
+    pub(crate) strict: bool,
+
+    /// When the program is not found, suggest subcommands named `<command><SEP><program>`
+    /// i.e. `-` finds `cargo-foo` for a program `foo`
+    #[arg(long, value_name = "SEP")]
+    pub(crate) subcommand_separator: Option<String>,
+
+    /// Report a problem if the program resolves from outside of these directories, can be repeated
+    #[arg(long, value_name = "DIR")]
+    pub(crate) allow_dir: Vec<PathBuf>,
+
+    /// Don't report this directory when it's empty, can be repeated
+    #[arg(long, value_name = "DIR")]
+    pub(crate) ignore_empty_dir: Vec<PathBuf>,
+
+    /// Project local directory to look for the program in when it's not found, relative to
+    /// the cwd and its parents, can be repeated. Default: `node_modules/.bin`
+    #[arg(long, value_name = "DIR")]
+    pub(crate) local_bin_dir: Vec<PathBuf>,
+
+    /// Don't look for the program in project local directories
+    #[arg(long, conflicts_with = "local_bin_dir")]
+    pub(crate) no_local_bin: bool,
+
+    /// Warn when the executable that will run is much older than a copy later on the PATH
+    #[arg(long)]
+    pub(crate) check_staleness: bool,
+
+    /// Show the score of every suggestion candidate and why it was or wasn't suggested
+    #[arg(long)]
+    pub(crate) suggest_debug: bool,
+
+    /// Only suggest valid executables, each candidate is checked on disk
+    #[arg(long)]
+    pub(crate) suggest_executables_only: bool,
+
+    /// When the whole PATH is wrapped in quotes, diagnose it with the quotes removed
+    #[arg(long)]
+    pub(crate) unquote_path: bool,
+
+    /// Show which directories were added or removed compared to the PATH of this process
+    #[arg(long)]
+    pub(crate) compare_process_path: bool,
+
+    /// Show the permission bits and owner of each file found i.e. `(mode 0644, owned by root:staff)`
+    #[arg(long)]
+    pub(crate) show_permissions: bool,
+
+    /// Follow symlinks to the program at most this many hops at a time instead of resolving
+    /// them all at once, which can block on an automount
+    #[arg(long, value_name = "N")]
+    pub(crate) max_symlink_hops: Option<usize>,
+
+    /// Warn when both a `.cmd` or `.bat` script wrapper and a native `.exe` are found
+    #[arg(long)]
+    pub(crate) check_script_wrappers: bool,
+
+    /// Run `/usr/bin/ldd` on the program to find shared libraries that can't be found, Linux only.
+    /// Some versions of `ldd` may execute the program
+    #[arg(long)]
+    pub(crate) check_shared_libraries: bool,
+
+    /// Also save the diagnosis to a `.whichp` file, which can be viewed later with `--load`
+    #[arg(long, value_name = "FILE")]
+    pub(crate) save: Option<PathBuf>,
+
+    /// Render a diagnosis saved with `--save` instead of diagnosing a program
+    #[arg(long, value_name = "FILE", conflicts_with = "save")]
+    pub(crate) load: Option<PathBuf>,
+
+    /// Lead with the most severe problem and how to fix it, then the full report.
+    /// Only applies to `--format text`
+    #[arg(long)]
+    pub(crate) actionable_first: bool,
+
+    /// How to render the diagnosis
+    #[arg(long, value_enum, default_value = "text")]
+    pub(crate) format: Format,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Format {
+    /// The full report, PATH parts in PATH order
+    Text,
+
+    /// PATH parts grouped by their state
+    Grouped,
+
+    /// Tab-separated `kind`, `path`, `state`, and `note` columns for found files,
+    /// PATH parts, and suggestions
+    Tsv,
 }
 
 #[cfg(test)]
@@ -0,0 +1,96 @@
+use serde::Deserialize;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+pub(crate) const FILE_NAME: &str = ".whichp.toml";
+
+/// Project configuration from a `.whichp.toml`, keys are named after `Which` options
+///
+/// Every key is optional. Options given on the command line take precedence.
+#[derive(Deserialize, Default, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Config {
+    pub(crate) guess_limit: Option<usize>,
+    pub(crate) subcommand_separator: Option<String>,
+    pub(crate) allowed_dirs: Option<Vec<PathBuf>>,
+    pub(crate) ignore_empty_dirs: Option<Vec<PathBuf>>,
+    pub(crate) local_bin_dirs: Option<Vec<PathBuf>>,
+    pub(crate) suggest_total_file_limit: Option<usize>,
+    pub(crate) max_symlink_hops: Option<usize>,
+    pub(crate) check_staleness: Option<bool>,
+    pub(crate) suggest_debug: Option<bool>,
+    pub(crate) suggest_executables_only: Option<bool>,
+    pub(crate) unquote_path: Option<bool>,
+    pub(crate) compare_process_path: Option<bool>,
+    pub(crate) show_permissions: Option<bool>,
+    pub(crate) check_script_wrappers: Option<bool>,
+    pub(crate) check_shared_libraries: Option<bool>,
+
+    /// Same as `cargo whichp --strict`
+    pub(crate) strict: Option<bool>,
+}
+
+/// Find and read the nearest `.whichp.toml` in `cwd` or its parents
+///
+/// Returns None when there is no config file. Relative directories in the
+/// file are relative to the directory holding it.
+pub(crate) fn discover(cwd: &Path) -> Result<Option<Config>, Error> {
+    let path = match cwd
+        .ancestors()
+        .map(|dir| dir.join(FILE_NAME))
+        .find(|path| path.is_file())
+    {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    let contents = std::fs::read_to_string(&path)?;
+    let config = parse(&contents)?;
+    let dir = path.parent().unwrap_or(cwd);
+    let relative_to_dir =
+        |dirs: Option<Vec<PathBuf>>| dirs.map(|dirs| dirs.iter().map(|d| dir.join(d)).collect());
+
+    let config = Config {
+        allowed_dirs: relative_to_dir(config.allowed_dirs),
+        ignore_empty_dirs: relative_to_dir(config.ignore_empty_dirs),
+        // `local_bin_dirs` stay relative, they're looked for in every parent of the cwd
+        ..config
+    };
+    Ok(Some(config))
+}
+
+pub(crate) fn parse(contents: &str) -> Result<Config, Error> {
+    toml::from_str(contents).map_err(|error| Error::new(ErrorKind::InvalidData, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_keys() {
+        let config = parse(
+            r#"
+guess_limit = 5
+allowed_dirs = ["/usr/bin", "bin"]
+check_staleness = true
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Config {
+                guess_limit: Some(5),
+                allowed_dirs: Some(vec![PathBuf::from("/usr/bin"), PathBuf::from("bin")]),
+                check_staleness: Some(true),
+                ..Config::default()
+            },
+            config
+        );
+    }
+
+    #[test]
+    fn parse_unknown_key() {
+        assert!(parse("guess_limt = 5").is_err());
+    }
+}
@@ -0,0 +1,105 @@
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+/// Read an environment dump, such as the output of `env -0` or `env`
+pub(crate) fn read(path: &Path) -> Result<Vec<(OsString, OsString)>, std::io::Error> {
+    std::fs::read(path).map(|contents| parse(&contents))
+}
+
+/// Parse `KEY=VALUE` pairs separated by NUL bytes, or by newlines when there are no NUL bytes
+///
+/// Entries without an `=` are skipped. Values are split on the first `=` only.
+pub(crate) fn parse(contents: &[u8]) -> Vec<(OsString, OsString)> {
+    let separator = if contents.contains(&b'\0') {
+        b'\0'
+    } else {
+        b'\n'
+    };
+
+    contents
+        .split(|byte| *byte == separator)
+        .map(|entry| entry.strip_suffix(b"\r").unwrap_or(entry))
+        .filter_map(|entry| {
+            let position = entry.iter().position(|byte| *byte == b'=')?;
+            let (key, value) = entry.split_at(position);
+            Some((os_string(key), os_string(&value[1..])))
+        })
+        .filter(|(key, _)| !key.is_empty())
+        .collect()
+}
+
+/// The value of `key` in the dump, the last one wins when it's set more than once
+pub(crate) fn get(env: &[(OsString, OsString)], key: &str) -> Option<OsString> {
+    env.iter()
+        .rev()
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value.clone())
+}
+
+/// Expand a leading `~` in each PATH part to `home`, the HOME from the dump
+///
+/// The PATH is returned unchanged when there's no `home` or the expanded
+/// parts cannot be joined back together.
+pub(crate) fn expand_home(path_env: &OsStr, home: Option<&OsStr>) -> OsString {
+    let home = match home {
+        Some(home) => Path::new(home),
+        None => return path_env.to_os_string(),
+    };
+    let parts = std::env::split_paths(path_env).map(|part| match part.strip_prefix("~") {
+        Ok(rest) if rest.as_os_str().is_empty() => home.to_path_buf(),
+        Ok(rest) => home.join(rest),
+        Err(_) => part,
+    });
+
+    std::env::join_paths(parts.collect::<Vec<PathBuf>>())
+        .unwrap_or_else(|_| path_env.to_os_string())
+}
+
+#[cfg(unix)]
+fn os_string(bytes: &[u8]) -> OsString {
+    use std::os::unix::ffi::OsStrExt;
+
+    std::ffi::OsStr::from_bytes(bytes).to_os_string()
+}
+
+#[cfg(not(unix))]
+fn os_string(bytes: &[u8]) -> OsString {
+    OsString::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_nul_separated() {
+        let env = parse(b"PATH=/usr/bin:/bin\0HOME=/home/x\0MULTI=a\nb=c\0");
+        assert_eq!(Some(OsString::from("/usr/bin:/bin")), get(&env, "PATH"));
+        assert_eq!(Some(OsString::from("/home/x")), get(&env, "HOME"));
+        assert_eq!(Some(OsString::from("a\nb=c")), get(&env, "MULTI"));
+    }
+
+    #[test]
+    fn parse_newline_separated() {
+        let env = parse(b"PATH=/usr/bin:/bin\r\nnot an entry\nHOME=/home/x\n");
+        assert_eq!(
+            vec![
+                (OsString::from("PATH"), OsString::from("/usr/bin:/bin")),
+                (OsString::from("HOME"), OsString::from("/home/x")),
+            ],
+            env
+        );
+        assert_eq!(None, get(&env, "PATHEXT"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn expand_home_in_path() {
+        let path = OsString::from("~/bin:~:/usr/bin:~other/bin");
+        assert_eq!(
+            OsString::from("/home/x/bin:/home/x:/usr/bin:~other/bin"),
+            expand_home(&path, Some(OsStr::new("/home/x")))
+        );
+        assert_eq!(path, expand_home(&path, None));
+    }
+}
@@ -2,13 +2,20 @@
 #![warn(unused_crate_dependencies)]
 
 mod cli;
+mod config;
+mod env_file;
+mod saved;
 
-use crate::cli::{Cli, WhichpArgs};
+use crate::cli::{Cli, Format, WhichpArgs};
+use crate::config::Config;
 use clap::Parser;
-use which_problem::Which;
+use std::ffi::OsString;
+use std::path::Path;
+use which_problem::{Program, SecurePath, Which};
 
 const COMMAND_SUCCESS: i32 = 0;
 const COMMAND_ERRORED: i32 = -1;
+const COMMAND_PROBLEMS: i32 = 1;
 
 fn main() {
     match Cli::parse() {
@@ -16,22 +23,111 @@ fn main() {
     }
 }
 
+/// How to print the diagnosis and when to exit with problems
+struct Output {
+    format: Format,
+    actionable_first: bool,
+    strict: bool,
+}
+
 fn handle_whichp(args: WhichpArgs) {
-    let path_env = match args.path {
-        Some(p) => Some(p),
-        None => Which::default().path_env,
+    let output = Output {
+        format: args.format,
+        actionable_first: args.actionable_first,
+        strict: args.strict,
+    };
+    if let Some(file) = &args.load {
+        match saved::load(file) {
+            Ok(saved) => print_and_exit(&saved.into_program(), &output),
+            Err(error) => {
+                eprintln!("Error, cannot read --load {}", file.display());
+                eprintln!("Details: {error}");
+
+                std::process::exit(COMMAND_ERRORED);
+            }
+        }
+    }
+
+    let env = read_env_file(args.env_file.as_deref());
+    let config = read_config(args.cwd.as_deref());
+
+    let path_env = match (args.path, &env) {
+        (Some(p), _) => Some(p),
+        (None, Some(env)) => env_file::get(env, "PATH")
+            .map(|path| env_file::expand_home(&path, env_file::get(env, "HOME").as_deref())),
+        (None, None) => Which::default().path_env,
+    };
+    let pathext = match &env {
+        Some(env) => env_file::get(env, "PATHEXT"),
+        None => Which::default().pathext,
+    };
+
+    let save = args.save;
+    let sudo = match args.secure_path {
+        Some(path) => Some(SecurePath::Explicit(path)),
+        None => args.sudo.then(SecurePath::default),
     };
 
     let which = Which {
-        program: args.program,
+        program: args.program.unwrap_or_default(),
         cwd: args.cwd,
         path_env,
-        guess_limit: args.suggest.unwrap_or(Which::default().guess_limit),
+        pathext,
+        guess_limit: args
+            .suggest
+            .or(config.guess_limit)
+            .unwrap_or(Which::default().guess_limit),
+        sudo,
+        subcommand_separator: args.subcommand_separator.or(config.subcommand_separator),
+        allowed_dirs: if args.allow_dir.is_empty() {
+            config.allowed_dirs
+        } else {
+            Some(args.allow_dir)
+        },
+        suggest_total_file_limit: config.suggest_total_file_limit,
+        check_staleness: args.check_staleness || config.check_staleness.unwrap_or_default(),
+        suggest_debug: args.suggest_debug || config.suggest_debug.unwrap_or_default(),
+        suggest_executables_only: args.suggest_executables_only
+            || config.suggest_executables_only.unwrap_or_default(),
+        unquote_path: args.unquote_path || config.unquote_path.unwrap_or_default(),
+        compare_process_path: args.compare_process_path
+            || config.compare_process_path.unwrap_or_default(),
+        show_permissions: args.show_permissions || config.show_permissions.unwrap_or_default(),
+        ignore_empty_dirs: if args.ignore_empty_dir.is_empty() {
+            config.ignore_empty_dirs.unwrap_or_default()
+        } else {
+            args.ignore_empty_dir
+        },
+        local_bin_dirs: if args.no_local_bin {
+            Vec::new()
+        } else if args.local_bin_dir.is_empty() {
+            config
+                .local_bin_dirs
+                .unwrap_or(Which::default().local_bin_dirs)
+        } else {
+            args.local_bin_dir
+        },
+        max_symlink_hops: args.max_symlink_hops.or(config.max_symlink_hops),
+        check_script_wrappers: args.check_script_wrappers
+            || config.check_script_wrappers.unwrap_or_default(),
+        check_shared_libraries: args.check_shared_libraries
+            || config.check_shared_libraries.unwrap_or_default(),
+    };
+    let output = Output {
+        strict: output.strict || config.strict.unwrap_or_default(),
+        ..output
     };
     match which.diagnose() {
         Ok(program) => {
-            println!("{program}");
-            std::process::exit(COMMAND_SUCCESS);
+            if let Some(file) = save {
+                if let Err(error) = saved::save(&file, &saved::Saved::new(&which, &program)) {
+                    eprintln!("Error, cannot write --save {}", file.display());
+                    eprintln!("Details: {error}");
+
+                    std::process::exit(COMMAND_ERRORED);
+                }
+            }
+            print_and_exit(&program, &output);
         }
         Err(error) => {
             eprintln!("Error, cannot continue");
@@ -41,3 +137,52 @@ fn handle_whichp(args: WhichpArgs) {
         }
     };
 }
+
+fn print_and_exit(program: &Program, output: &Output) -> ! {
+    match output.format {
+        Format::Text if output.actionable_first => {
+            println!("{}", program.render_actionable_first());
+        }
+        Format::Text => println!("{program}"),
+        Format::Grouped => println!("{}", program.render_grouped()),
+        Format::Tsv => print!("{}", program.render_tsv()),
+    }
+    if output.strict && !program.problems().is_empty() {
+        std::process::exit(COMMAND_PROBLEMS);
+    }
+    std::process::exit(COMMAND_SUCCESS);
+}
+
+/// The nearest `.whichp.toml` config, or the default when there is none
+fn read_config(cwd: Option<&Path>) -> Config {
+    let cwd = match cwd {
+        Some(cwd) => cwd.to_path_buf(),
+        None => match std::env::current_dir() {
+            Ok(cwd) => cwd,
+            Err(_) => return Config::default(),
+        },
+    };
+
+    match config::discover(&cwd) {
+        Ok(config) => config.unwrap_or_default(),
+        Err(error) => {
+            eprintln!("Error, cannot read {}", config::FILE_NAME);
+            eprintln!("Details: {error}");
+
+            std::process::exit(COMMAND_ERRORED);
+        }
+    }
+}
+
+/// The `--env-file` dump, exits when it cannot be read
+fn read_env_file(file: Option<&Path>) -> Option<Vec<(OsString, OsString)>> {
+    match file.map(env_file::read).transpose() {
+        Ok(env) => env,
+        Err(error) => {
+            eprintln!("Error, cannot read --env-file");
+            eprintln!("Details: {error}");
+
+            std::process::exit(COMMAND_ERRORED);
+        }
+    }
+}
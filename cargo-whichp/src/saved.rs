@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::path::Path;
+use which_problem::{Program, SavedProgram, Which};
+
+/// Format of `.whichp` files, bumped when a file written by an older version can no longer be read
+const VERSION: u32 = 1;
+
+/// A diagnosis saved to a `.whichp` file so it can be viewed later without access to the original machine
+///
+/// Names and paths are stored as UTF-8, bytes that are not valid UTF-8 are replaced.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Saved {
+    /// See `VERSION`
+    version: u32,
+
+    /// The program that was diagnosed
+    name: String,
+
+    /// The PATH that was diagnosed, None when it was not set
+    path: Option<String>,
+
+    /// The working directory given with `--cwd`, None for the process working directory
+    cwd: Option<String>,
+
+    /// The result of diagnosing the program
+    program: SavedProgram,
+}
+
+impl Saved {
+    pub(crate) fn new(which: &Which, program: &Program) -> Self {
+        Saved {
+            version: VERSION,
+            name: which.program.to_string_lossy().into_owned(),
+            path: which
+                .path_env
+                .as_ref()
+                .map(|path| path.to_string_lossy().into_owned()),
+            cwd: which
+                .cwd
+                .as_ref()
+                .map(|cwd| cwd.to_string_lossy().into_owned()),
+            program: SavedProgram::from(program),
+        }
+    }
+
+    pub(crate) fn into_program(self) -> Program {
+        Program::from(self.program)
+    }
+}
+
+/// Why a `.whichp` file could not be written or read
+#[derive(Debug)]
+pub(crate) enum SavedError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    UnsupportedVersion(u32),
+}
+
+impl Display for SavedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SavedError::Io(error) => write!(f, "{error}"),
+            SavedError::Json(error) => write!(f, "Invalid .whichp file: {error}"),
+            SavedError::UnsupportedVersion(version) => write!(
+                f,
+                "Unsupported .whichp file version {version}, expected {VERSION}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SavedError {}
+
+pub(crate) fn save(path: &Path, saved: &Saved) -> Result<(), SavedError> {
+    let contents = serde_json::to_string_pretty(saved).map_err(SavedError::Json)?;
+    std::fs::write(path, contents).map_err(SavedError::Io)
+}
+
+pub(crate) fn load(path: &Path) -> Result<Saved, SavedError> {
+    let contents = std::fs::read_to_string(path).map_err(SavedError::Io)?;
+    let version = serde_json::from_str::<Version>(&contents)
+        .map_err(SavedError::Json)?
+        .version;
+    if version != VERSION {
+        return Err(SavedError::UnsupportedVersion(version));
+    }
+
+    serde_json::from_str(&contents).map_err(SavedError::Json)
+}
+
+/// Read before the rest of the file, so files from other versions get a clear error
+#[derive(Deserialize)]
+struct Version {
+    version: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_rejects_other_versions() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let file = tmp_dir.path().join("diagnosis.whichp");
+        std::fs::write(&file, r#"{"version": 2}"#).unwrap();
+
+        assert!(matches!(
+            load(&file),
+            Err(SavedError::UnsupportedVersion(2))
+        ));
+    }
+}
@@ -0,0 +1,291 @@
+// Relies on unix permissions
+#![cfg(unix)]
+
+use std::ffi::OsString;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn make_executable(file: &Path) {
+    let perms = std::fs::metadata(file).unwrap().permissions();
+    let mode = perms.mode() | 0o111;
+    std::fs::set_permissions(file, std::fs::Permissions::from_mode(mode)).unwrap();
+}
+
+fn whichp<I, S>(args: I) -> Output
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<std::ffi::OsStr>,
+{
+    Command::new(env!("CARGO_BIN_EXE_cargo-whichp"))
+        .arg("whichp")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn strict_fails_when_found_with_duplicate() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let dir = tmp_dir.path();
+    let tmp_dir_two = tempfile::tempdir().unwrap();
+    let dir_two = tmp_dir_two.path();
+    for dir in [dir, dir_two] {
+        let file = dir.join("lol");
+        std::fs::write(&file, "contents").unwrap();
+        make_executable(&file);
+    }
+    let path = [dir.as_os_str(), dir_two.as_os_str()].join(&OsString::from(":"));
+
+    let output = whichp([
+        OsString::from("lol"),
+        OsString::from("--path"),
+        path.clone(),
+    ]);
+    assert!(output.status.success());
+
+    let output = whichp([
+        OsString::from("lol"),
+        OsString::from("--path"),
+        path,
+        OsString::from("--strict"),
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains(r#"Program "lol" found at"#));
+}
+
+#[test]
+fn format_grouped() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let dir = tmp_dir.path();
+    let file = dir.join("lol");
+    std::fs::write(&file, "contents").unwrap();
+    make_executable(&file);
+    let missing = dir.join("nope");
+    let path = [missing.as_os_str(), dir.as_os_str()].join(&OsString::from(":"));
+
+    let output = whichp([
+        OsString::from("lol"),
+        OsString::from("--path"),
+        path,
+        OsString::from("--format"),
+        OsString::from("grouped"),
+    ]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[MISSING] 1 directory"));
+    assert!(stdout.contains(&format!("  > {dir:?}")));
+}
+
+#[test]
+fn env_file_provides_path() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let dir = tmp_dir.path();
+    let file = dir.join("lol");
+    std::fs::write(&file, "contents").unwrap();
+    make_executable(&file);
+
+    let env_file = dir.join("env");
+    let mut contents = b"HOME=/home/nobody\0PATH=".to_vec();
+    contents.extend_from_slice(std::os::unix::ffi::OsStrExt::as_bytes(dir.as_os_str()));
+    contents.extend_from_slice(b"\0");
+    std::fs::write(&env_file, contents).unwrap();
+
+    let output = whichp([
+        OsString::from("lol"),
+        OsString::from("--env-file"),
+        env_file.into_os_string(),
+    ]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains(&format!("Program \"lol\" found at {file:?}")));
+}
+
+#[test]
+fn save_then_load_without_filesystem() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let dir = tmp_dir.path().join("bin");
+    std::fs::create_dir(&dir).unwrap();
+    let file = dir.join("lol");
+    std::fs::write(&file, "contents").unwrap();
+    make_executable(&file);
+    let saved = tmp_dir.path().join("diagnosis.whichp");
+
+    let output = whichp([
+        OsString::from("lol"),
+        OsString::from("--path"),
+        dir.clone().into_os_string(),
+        OsString::from("--save"),
+        saved.clone().into_os_string(),
+    ]);
+    assert!(output.status.success());
+    assert!(saved.exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let loaded = whichp([OsString::from("--load"), saved.into_os_string()]);
+    assert!(loaded.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&loaded.stdout)
+    );
+    assert!(String::from_utf8_lossy(&loaded.stdout)
+        .contains(&format!("Program \"lol\" found at {file:?}")));
+}
+
+#[test]
+fn format_tsv() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let dir = tmp_dir.path().join("tab\there");
+    std::fs::create_dir(&dir).unwrap();
+    let file = dir.join("lol");
+    std::fs::write(&file, "contents").unwrap();
+    make_executable(&file);
+
+    let output = whichp([
+        OsString::from("lol"),
+        OsString::from("--path"),
+        dir.clone().into_os_string(),
+        OsString::from("--format"),
+        OsString::from("tsv"),
+    ]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let rows = stdout
+        .lines()
+        .map(|line| line.split('\t').collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert!(rows.iter().all(|row| row.len() == 4));
+
+    let escaped = dir.to_string_lossy().replace('\t', "\\t");
+    assert_eq!(
+        vec![
+            vec!["kind", "path", "state", "note"],
+            vec!["file", &format!("{escaped}/lol"), "OK", "will run"],
+            vec!["path", &escaped, "OK", ""],
+        ],
+        rows
+    );
+}
+
+#[test]
+fn config_file_in_parent_dir() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let project = tmp_dir.path();
+    let cwd = project.join("nested");
+    std::fs::create_dir(&cwd).unwrap();
+    let bin = project.join("bin");
+    std::fs::create_dir(&bin).unwrap();
+    let file = bin.join("lol");
+    std::fs::write(&file, "contents").unwrap();
+    make_executable(&file);
+    std::fs::write(
+        project.join(".whichp.toml"),
+        "allowed_dirs = [\"approved\"]\nstrict = true\n",
+    )
+    .unwrap();
+
+    let args = [
+        OsString::from("lol"),
+        OsString::from("--path"),
+        bin.clone().into_os_string(),
+        OsString::from("--cwd"),
+        cwd.into_os_string(),
+    ];
+    let output = whichp(args.clone());
+    assert_eq!(Some(1), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&format!(
+        "Program resolves from {bin:?} which is not one of the allowed directories"
+    )));
+
+    // Flags take precedence over the file
+    let output = whichp(
+        args.into_iter()
+            .chain([OsString::from("--allow-dir"), bin.into_os_string()]),
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn actionable_first() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let dir = tmp_dir.path();
+
+    let output = whichp([
+        OsString::from("lol"),
+        OsString::from("--path"),
+        dir.as_os_str().to_os_string(),
+        OsString::from("--actionable-first"),
+    ]);
+    assert!(output.status.success());
+    assert_eq!(
+        Some("Error: Program not found"),
+        String::from_utf8_lossy(&output.stdout).lines().next()
+    );
+}
+
+#[test]
+fn env_file_provides_pathext_and_home() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let home = tmp_dir.path();
+    let dir = home.join("bin");
+    std::fs::create_dir(&dir).unwrap();
+    let file = dir.join("lol.foo");
+    std::fs::write(&file, "contents").unwrap();
+    make_executable(&file);
+
+    let env_file = home.join("env");
+    let mut contents = b"PATHEXT=.foo\0PATH=~/bin\0HOME=".to_vec();
+    contents.extend_from_slice(std::os::unix::ffi::OsStrExt::as_bytes(home.as_os_str()));
+    contents.extend_from_slice(b"\0");
+    std::fs::write(&env_file, contents).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-whichp"))
+        .arg("whichp")
+        .arg("lol")
+        .arg("--env-file")
+        .arg(&env_file)
+        .env("HOME", "/home/nobody")
+        .env("PATHEXT", ".bar")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains(&format!("Program \"lol\" found at {file:?}")));
+}
+
+#[test]
+fn no_local_bin() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let project = tmp_dir.path();
+    let bin = project.join("node_modules").join(".bin");
+    std::fs::create_dir_all(&bin).unwrap();
+    let file = bin.join("lol");
+    std::fs::write(&file, "contents").unwrap();
+    make_executable(&file);
+    let path = project.join("empty");
+    std::fs::create_dir(&path).unwrap();
+
+    let args = [
+        OsString::from("lol"),
+        OsString::from("--path"),
+        path.into_os_string(),
+        OsString::from("--cwd"),
+        project.as_os_str().to_os_string(),
+    ];
+    let found = format!("Info: Found {file:?} in a project local directory");
+    let output = whichp(args.clone());
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&found));
+
+    let output = whichp(
+        args.clone()
+            .into_iter()
+            .chain([OsString::from("--no-local-bin")]),
+    );
+    assert!(!String::from_utf8_lossy(&output.stdout).contains(&found));
+
+    // An empty list in the config file disables it too
+    std::fs::write(project.join(".whichp.toml"), "local_bin_dirs = []\n").unwrap();
+    let output = whichp(args);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains(&found));
+}
@@ -0,0 +1,43 @@
+use crate::problem::Severity;
+use crate::program::Program;
+use std::fmt::Write;
+
+impl Program {
+    /// Render the most severe problem and how to fix it first, followed by the full report
+    ///
+    /// The first line names the problem from `problems()` that needs attention
+    /// most, then the commands from `remediation_commands()` when there are any.
+    /// When nothing is wrong the first line says so. The commands are not
+    /// repeated in the report.
+    #[must_use]
+    pub fn render_actionable_first(&self) -> String {
+        let mut out = String::new();
+        // Writing to a String cannot fail
+        let _ = self.write_actionable_first(&mut out);
+        out
+    }
+
+    fn write_actionable_first(&self, f: &mut dyn Write) -> std::fmt::Result {
+        match self.problems().first() {
+            Some(problem) => {
+                let label = match problem.severity() {
+                    Severity::Error => "Error",
+                    Severity::Warning => "Warning",
+                };
+                writeln!(f, "{label}: {problem}")?;
+
+                let commands = self.remediation_commands();
+                if !commands.is_empty() {
+                    f.write_str("To fix, run:\n")?;
+                    for command in commands {
+                        writeln!(f, "  {command}")?;
+                    }
+                }
+            }
+            None => f.write_str("Nothing to fix, no problems found\n")?,
+        }
+        f.write_char('\n')?;
+
+        self.write_report(f, true, false)
+    }
+}
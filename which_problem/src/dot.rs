@@ -0,0 +1,77 @@
+use crate::program::Program;
+use crate::symlink;
+use std::ffi::OsStr;
+use std::fmt::Write;
+
+impl Program {
+    /// Render how the program resolves as a Graphviz DOT graph
+    ///
+    /// Nodes are PATH parts, files matching the program name, and the targets
+    /// of any symlinks. A "searched" edge connects a PATH part to the file found
+    /// in it and "resolves to" edges follow each symlink hop.
+    ///
+    /// Pipe the output into `dot -Tpng` to render an image.
+    #[must_use]
+    pub fn render_dot(&self) -> String {
+        let mut out = String::new();
+        // Writing to a String cannot fail
+        let _ = self.write_dot(&mut out);
+        out
+    }
+
+    fn write_dot(&self, f: &mut dyn Write) -> std::fmt::Result {
+        let executable = self.executable();
+
+        writeln!(f, "digraph which {{")?;
+        writeln!(f, "  rankdir=LR;")?;
+        for part in &self.path_parts {
+            writeln!(
+                f,
+                "  {id} [shape=box, label={label}];",
+                id = quote(&part.absolute),
+                label = quote(format!("{} [{}]", part.original.display(), part.state))
+            )?;
+        }
+
+        for file in &self.found_files {
+            let style = if Some(file) == executable {
+                ", style=bold"
+            } else {
+                ""
+            };
+            writeln!(
+                f,
+                "  {id} [label={label}{style}];",
+                id = quote(&file.path),
+                label = quote(format!("{} [{}]", file.path.display(), file.state))
+            )?;
+            if let Some(parent) = file.path.parent() {
+                writeln!(
+                    f,
+                    "  {} -> {} [label=\"searched\"];",
+                    quote(parent),
+                    quote(&file.path)
+                )?;
+            }
+
+            let chain = symlink::chain(&file.path, symlink::MAX_HOPS);
+            for (from, to) in chain.iter().zip(chain.iter().skip(1)) {
+                writeln!(
+                    f,
+                    "  {} -> {} [label=\"resolves to\"];",
+                    quote(from),
+                    quote(to)
+                )?;
+            }
+        }
+        writeln!(f, "}}")?;
+
+        Ok(())
+    }
+}
+
+/// Quote a string as a DOT ID
+fn quote<S: AsRef<OsStr>>(value: S) -> String {
+    let value = value.as_ref().to_string_lossy();
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
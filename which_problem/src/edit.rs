@@ -0,0 +1,214 @@
+use std::fmt::Display;
+
+/// Suggestions further than this many edits away are not explained
+pub(crate) const MAX_EXPLAINED_EDITS: usize = 2;
+
+/// A single change that turns what was typed into a suggestion
+///
+/// Positions are character indexes into what was typed, starting at 0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Edit {
+    Insert {
+        position: usize,
+        char: char,
+    },
+    Delete {
+        position: usize,
+        char: char,
+    },
+    Substitute {
+        position: usize,
+        from: char,
+        to: char,
+    },
+    Transpose {
+        position: usize,
+        first: char,
+        second: char,
+    },
+}
+
+impl Display for Edit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Edit::Insert { position, char } => {
+                write!(f, "insert '{char}' at position {position}")
+            }
+            Edit::Delete { position, char } => {
+                write!(f, "delete '{char}' at position {position}")
+            }
+            Edit::Substitute { position, from, to } => {
+                write!(f, "replace '{from}' with '{to}' at position {position}")
+            }
+            Edit::Transpose {
+                position,
+                first,
+                second,
+            } => write!(f, "swap '{first}' and '{second}' at position {position}"),
+        }
+    }
+}
+
+/// The edits that turn `typed` into `target`, in order
+///
+/// Uses optimal string alignment distance, so swapping two neighboring
+/// characters counts as one edit. Returns None when the strings are equal or
+/// more than `max` edits apart.
+pub(crate) fn edits(typed: &str, target: &str, max: usize) -> Option<Vec<Edit>> {
+    let typed = typed.chars().collect::<Vec<_>>();
+    let target = target.chars().collect::<Vec<_>>();
+    if typed.len().abs_diff(target.len()) > max {
+        return None;
+    }
+
+    let mut dist = vec![vec![0; target.len() + 1]; typed.len() + 1];
+    for (i, row) in dist.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in dist[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=typed.len() {
+        for j in 1..=target.len() {
+            let cost = usize::from(typed[i - 1] != target[j - 1]);
+            dist[i][j] = (dist[i - 1][j] + 1)
+                .min(dist[i][j - 1] + 1)
+                .min(dist[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && typed[i - 1] == target[j - 2] && typed[i - 2] == target[j - 1] {
+                dist[i][j] = dist[i][j].min(dist[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    let distance = dist[typed.len()][target.len()];
+    if distance == 0 || distance > max {
+        return None;
+    }
+
+    let mut out = Vec::new();
+    let (mut i, mut j) = (typed.len(), target.len());
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 && typed[i - 1] == target[j - 1] && dist[i][j] == dist[i - 1][j - 1] {
+            i -= 1;
+            j -= 1;
+        } else if i > 1
+            && j > 1
+            && typed[i - 1] == target[j - 2]
+            && typed[i - 2] == target[j - 1]
+            && dist[i][j] == dist[i - 2][j - 2] + 1
+        {
+            out.push(Edit::Transpose {
+                position: i - 2,
+                first: typed[i - 2],
+                second: typed[i - 1],
+            });
+            i -= 2;
+            j -= 2;
+        } else if i > 0 && j > 0 && dist[i][j] == dist[i - 1][j - 1] + 1 {
+            out.push(Edit::Substitute {
+                position: i - 1,
+                from: typed[i - 1],
+                to: target[j - 1],
+            });
+            i -= 1;
+            j -= 1;
+        } else if i > 0 && dist[i][j] == dist[i - 1][j] + 1 {
+            out.push(Edit::Delete {
+                position: i - 1,
+                char: typed[i - 1],
+            });
+            i -= 1;
+        } else {
+            out.push(Edit::Insert {
+                position: i,
+                char: target[j - 1],
+            });
+            j -= 1;
+        }
+    }
+    out.reverse();
+
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_insert() {
+        assert_eq!(
+            Some(vec![Edit::Insert {
+                position: 1,
+                char: 'u'
+            }]),
+            edits("bndle", "bundle", MAX_EXPLAINED_EDITS)
+        );
+        assert_eq!(
+            "insert 'u' at position 1",
+            edits("bndle", "bundle", MAX_EXPLAINED_EDITS).unwrap()[0].to_string()
+        );
+    }
+
+    #[test]
+    fn check_delete() {
+        assert_eq!(
+            Some(vec![Edit::Delete {
+                position: 4,
+                char: 'x'
+            }]),
+            edits("bundxle", "bundle", MAX_EXPLAINED_EDITS)
+        );
+    }
+
+    #[test]
+    fn check_substitute() {
+        assert_eq!(
+            Some(vec![Edit::Substitute {
+                position: 0,
+                from: 'v',
+                to: 'b'
+            }]),
+            edits("vundle", "bundle", MAX_EXPLAINED_EDITS)
+        );
+    }
+
+    #[test]
+    fn check_transpose() {
+        assert_eq!(
+            Some(vec![Edit::Transpose {
+                position: 1,
+                first: 'n',
+                second: 'u'
+            }]),
+            edits("bnudle", "bundle", MAX_EXPLAINED_EDITS)
+        );
+        assert_eq!(
+            "swap 'n' and 'u' at position 1",
+            edits("bnudle", "bundle", MAX_EXPLAINED_EDITS).unwrap()[0].to_string()
+        );
+    }
+
+    #[test]
+    fn check_multiple_edits() {
+        assert_eq!(
+            Some(vec![
+                Edit::Insert {
+                    position: 1,
+                    char: 'u'
+                },
+                Edit::Delete {
+                    position: 5,
+                    char: 's'
+                },
+            ]),
+            edits("bndles", "bundle", MAX_EXPLAINED_EDITS)
+        );
+    }
+
+    #[test]
+    fn check_too_far_or_equal() {
+        assert_eq!(None, edits("rofl", "bundle", MAX_EXPLAINED_EDITS));
+        assert_eq!(None, edits("bundle", "bundle", MAX_EXPLAINED_EDITS));
+    }
+}
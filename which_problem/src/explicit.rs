@@ -0,0 +1,38 @@
+use crate::path_part::PathPart;
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+
+/// A program given as a path i.e. `/opt/x/bin/x` or `./bin/x` rather than a bare name
+///
+/// The file is checked directly instead of searching the PATH, the PATH is
+/// still analyzed to explain whether the bare name would resolve.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ExplicitPath {
+    /// The directory holding the program
+    pub(crate) dir: PathPart,
+
+    /// The file name of the program, what would be typed without the directory
+    pub(crate) name: OsString,
+
+    /// True when `dir` is one of the PATH parts
+    pub(crate) on_path: bool,
+}
+
+/// True when the program contains a directory, so it's not looked up on the PATH
+pub(crate) fn is_explicit(program: &OsStr) -> bool {
+    Path::new(program).components().count() > 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_is_explicit() {
+        assert!(is_explicit(OsStr::new("/opt/x/bin/x")));
+        assert!(is_explicit(OsStr::new("./x")));
+        assert!(is_explicit(OsStr::new("bin/x")));
+        assert!(!is_explicit(OsStr::new("x")));
+        assert!(!is_explicit(OsStr::new("")));
+    }
+}
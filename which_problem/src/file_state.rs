@@ -1,40 +1,92 @@
+use crate::symlink;
 use is_executable::IsExecutable;
 use std::fmt::Display;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Return the state of a file inside of a PATH directory
-pub(crate) fn file_state(path: &Path) -> FileState {
-    if path.is_symlink() {
-        match symlink_state(path) {
+///
+/// Symlinks are followed, a symlink is only `Valid` if the file
+/// it points to is an executable file.
+///
+/// Example:
+///
+/// ```rust
+/// use std::path::Path;
+/// use which_problem::{file_state, FileState};
+///
+/// assert_eq!(FileState::Valid, file_state(Path::new("/bin/sh")));
+/// assert_eq!(FileState::Missing, file_state(Path::new("/does/not/exist")));
+/// ```
+#[must_use]
+pub fn file_state(path: &Path) -> FileState {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => match symlink_state(path) {
             SymlinkState::Valid => FileState::Valid,
             _ => FileState::BadSymlink,
+        },
+        // Directories have the execute (search) bit, they must be
+        // ruled out before checking for executable permissions
+        Ok(metadata) if metadata.is_dir() => FileState::IsDir,
+        Ok(_) if path.is_executable() => FileState::Valid,
+        Ok(_) => FileState::NotExecutable,
+        Err(_) => FileState::Missing,
+    }
+}
+
+/// Same as `file_state`, but when `max_hops` is set symlinks are followed one hop
+/// at a time instead of with `canonicalize`, which can block on an automount
+///
+/// A chain that is still a symlink after `max_hops` hops is `TooManySymlinkHops`,
+/// otherwise the state is the state of the file at the end of the chain.
+pub(crate) fn bounded_file_state(path: &Path, max_hops: Option<usize>) -> FileState {
+    let max_hops = match max_hops {
+        Some(max_hops) => max_hops,
+        None => return file_state(path),
+    };
+
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => {
+            // Each hop is only followed if the link before it can be read
+            let chain = symlink::chain(path, max_hops);
+            let last = chain.last().map_or(path, PathBuf::as_path);
+            match std::fs::symlink_metadata(last) {
+                Ok(metadata) if metadata.file_type().is_symlink() => FileState::TooManySymlinkHops,
+                Ok(_) => file_state(last),
+                Err(_) => FileState::BadSymlink,
+            }
         }
-    } else if path.exists() {
-        if path.is_dir() {
-            FileState::IsDir
-        } else if path.is_executable() {
-            FileState::Valid
-        } else {
-            FileState::NotExecutable
-        }
-    } else {
-        FileState::Missing
+        _ => file_state(path),
     }
 }
 
 /// All the various states a file inside of a PATH directory
 /// can hold.
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
-pub(crate) enum FileState {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FileState {
+    /// An executable file, or a symlink to one
     Valid,
+
+    /// Entry is a directory
     IsDir,
+
+    /// Nothing exists at this path
     Missing,
+
+    /// Symlink that does not resolve to an executable file
     BadSymlink,
+
+    /// File exists but does not have executable permissions
     NotExecutable,
+
+    /// Symlink chain longer than `Which::max_symlink_hops`, it was not followed to the end
+    TooManySymlinkHops,
 }
 
 impl FileState {
-    pub(crate) fn details(&self) -> String {
+    /// Human readable explanation of the state
+    #[must_use]
+    pub fn details(&self) -> String {
         match self {
             FileState::Valid => {
                 "File found matching program name with executable permissions. Valid executable."
@@ -47,6 +99,9 @@ impl FileState {
             FileState::NotExecutable => {
                 "File found matching program name, but it does not have executable permissions"
             }
+            FileState::TooManySymlinkHops => {
+                "File found matching program name, but it's a symlink chain with more hops than allowed"
+            }
         }
         .to_string()
     }
@@ -60,6 +115,7 @@ impl Display for FileState {
             FileState::Missing => f.write_str("MISSING"),
             FileState::BadSymlink => f.write_str("BAD SYM"),
             FileState::NotExecutable => f.write_str("NOT EXE"),
+            FileState::TooManySymlinkHops => f.write_str("TOO MANY HOPS"),
         }
     }
 }
@@ -71,7 +127,9 @@ fn symlink_state(path: &Path) -> SymlinkState {
         match file_state(&link) {
             FileState::IsDir => SymlinkState::IsDir,
             FileState::Valid => SymlinkState::Valid,
-            FileState::Missing | FileState::BadSymlink => SymlinkState::Missing,
+            FileState::Missing | FileState::BadSymlink | FileState::TooManySymlinkHops => {
+                SymlinkState::Missing
+            }
             FileState::NotExecutable => SymlinkState::NotExecutable,
         }
     } else {
@@ -0,0 +1,76 @@
+use crate::problem::Problem;
+use crate::program::Program;
+use itertools::Itertools;
+use std::fmt::Write;
+
+impl Program {
+    /// Render PATH parts grouped by their state instead of in PATH order
+    ///
+    /// Groups are listed in the order their state first appears on the PATH,
+    /// each with a count. Within a group parts stay in PATH order and the
+    /// directory the program was found in is marked with `>`.
+    #[must_use]
+    pub fn render_grouped(&self) -> String {
+        let mut out = String::new();
+        // Writing to a String cannot fail
+        let _ = self.write_grouped(&mut out);
+        out
+    }
+
+    #[allow(clippy::unnecessary_debug_formatting)]
+    fn write_grouped(&self, f: &mut dyn Write) -> std::fmt::Result {
+        let name = &self.name;
+        let executable = self.executable();
+        match executable {
+            Some(found) => writeln!(f, "Program {name:?} found at {:?}", found.path)?,
+            None => writeln!(f, "Program {name:?} not found")?,
+        }
+        f.write_char('\n')?;
+
+        if self.path_unset {
+            return writeln!(f, "Warning: {}", Problem::UnsetPath);
+        } else if self.path_parts.is_empty() {
+            return writeln!(f, "Warning: {}", Problem::EmptyPath);
+        }
+
+        let winning_dir = executable.and_then(|found| found.path.parent());
+        f.write_str("Info: The following directories on PATH were searched, grouped by state:\n")?;
+        for (state, parts) in self
+            .path_parts
+            .iter()
+            .into_group_map_by(|part| part.state.clone())
+            .into_iter()
+            .sorted_by_key(|(state, _)| {
+                self.path_parts
+                    .iter()
+                    .position(|part| &part.state == state)
+                    .unwrap_or_default()
+            })
+        {
+            let count = parts.len();
+            let noun = if count == 1 {
+                "directory"
+            } else {
+                "directories"
+            };
+            writeln!(f, "[{state}] {count} {noun} - {}", state.details())?;
+            for part in parts {
+                let marker = if winning_dir == Some(part.absolute.as_path()) {
+                    '>'
+                } else {
+                    '-'
+                };
+                write!(f, "  {marker} {:?}", part.original)?;
+                if part.relative {
+                    write!(f, " (relative from {:?})", part.cwd)?;
+                }
+                f.write_char('\n')?;
+                if let Some(hint) = &part.hint {
+                    writeln!(f, "    Help: {hint}")?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -25,30 +25,62 @@
 ///     })
 ///     .unwrap();
 /// ```
+mod actionable;
+mod dot;
+mod edit;
+mod explicit;
 mod file_state;
+mod grouped;
+mod listing;
+mod normalize;
+mod path_diff;
 mod path_part;
 mod path_with_state;
+mod problem;
 mod program;
+#[cfg(feature = "serde")]
+mod saved;
+mod shared_library;
+mod sudo;
 mod suggest;
+mod symlink;
+mod table;
+mod tsv;
+#[cfg(feature = "watch")]
+mod watch;
 mod which;
 
 // Primary input interface
-pub use crate::which::Which;
+pub use crate::sudo::SecurePath;
+pub use crate::which::{Which, WhichConfigWarning};
+
+#[cfg(feature = "watch")]
+pub use crate::watch::WhichWatcher;
 
 // Primary output interface
+pub use crate::path_part::PartState;
+pub use crate::problem::{Problem, Severity};
 pub use crate::program::Program;
+pub use crate::suggest::{SuggestionDebug, SuggestionDisposition};
+
+// Save a diagnosis to view later
+#[cfg(feature = "serde")]
+pub use crate::saved::SavedProgram;
+
+// Inspect a single file
+pub use crate::file_state::{file_state, FileState};
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::file_state::{file_state, FileState};
     use crate::path_with_state::PathWithState;
     use crate::which::Which;
     use is_executable::IsExecutable;
-    use path_part::PartState;
+    use path_part::PartHint;
+    use std::collections::HashMap;
     use std::ffi::OsString;
     use std::os::unix::fs::PermissionsExt;
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
 
     fn make_executable(file: &Path) {
         let perms = std::fs::metadata(file).unwrap().permissions();
@@ -74,7 +106,7 @@ mod tests {
 
         let program = Which {
             program,
-            path_env: Some(vec![dir.as_os_str(), dir_two.as_os_str()].join(&OsString::from(":"))),
+            path_env: Some([dir.as_os_str(), dir_two.as_os_str()].join(&OsString::from(":"))),
             ..Which::default()
         }
         .diagnose()
@@ -143,6 +175,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn check_executable_dir_is_not_valid() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let program_dir = dir.join("lol");
+        let name = OsString::from(program_dir.file_name().unwrap());
+
+        std::fs::create_dir(&program_dir).unwrap();
+        std::fs::set_permissions(&program_dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert_eq!(FileState::IsDir, file_state(&program_dir));
+
+        let program = Which {
+            program: name,
+            path_env: Some(dir.as_os_str().into()),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        assert_eq!(
+            vec![PathWithState {
+                path: program_dir,
+                state: FileState::IsDir,
+            }],
+            program.found_files
+        );
+        assert_eq!(Some(&Problem::NotFound), program.problems().first());
+    }
+
     #[test]
     fn check_symlink_file() {
         let tmp_dir = tempfile::tempdir().unwrap();
@@ -171,6 +233,121 @@ mod tests {
         );
     }
 
+    #[test]
+    fn check_dot_graph() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("lol");
+        let name = OsString::from(file.file_name().unwrap());
+
+        let tmp_dir_two = tempfile::tempdir().unwrap();
+        let target = tmp_dir_two.path().join("real");
+        std::fs::write(&target, "contents").unwrap();
+        make_executable(&target);
+        std::os::unix::fs::symlink(&target, &file).unwrap();
+
+        let program = Which {
+            program: name,
+            path_env: Some(dir.as_os_str().into()),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        let dot = program.render_dot();
+        assert!(dot.starts_with("digraph which {"));
+        assert!(dot.contains(&format!(r#""{}" [shape=box"#, dir.display())));
+        assert!(dot.contains(&format!(
+            r#""{}" -> "{}" [label="searched"];"#,
+            dir.display(),
+            file.display()
+        )));
+        assert!(dot.contains(&format!(
+            r#""{}" -> "{}" [label="resolves to"];"#,
+            file.display(),
+            target.display()
+        )));
+    }
+
+    #[test]
+    fn check_compact_table() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("lol");
+        let name = OsString::from(file.file_name().unwrap());
+        let missing = dir.join("nope");
+
+        std::fs::write(&file, "contents").unwrap();
+        make_executable(&file);
+
+        let program = Which {
+            program: name,
+            path_env: Some([missing.as_os_str(), dir.as_os_str()].join(&OsString::from(":"))),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        let table = program.render_compact_table();
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "  STATE  FILE".to_string(),
+                format!("> OK     {file:?}"),
+                String::new(),
+                "  STATE    DIRECTORY".to_string(),
+                format!("- MISSING  {missing:?}"),
+                format!("> OK       {dir:?}"),
+            ],
+            lines[2..]
+        );
+    }
+
+    #[test]
+    fn check_grouped() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("lol");
+        let name = OsString::from(file.file_name().unwrap());
+        let missing = dir.join("nope");
+        let missing_two = dir.join("also_nope");
+        let tmp_dir_two = tempfile::tempdir().unwrap();
+        let empty = tmp_dir_two.path();
+
+        std::fs::write(&file, "contents").unwrap();
+        make_executable(&file);
+
+        let path_env = [
+            missing.as_os_str(),
+            dir.as_os_str(),
+            empty.as_os_str(),
+            missing_two.as_os_str(),
+        ]
+        .join(&OsString::from(":"));
+        let program = Which {
+            program: name,
+            path_env: Some(path_env),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        let grouped = program.render_grouped();
+        let lines = grouped.lines().collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                format!("[MISSING] 2 directories - {}", PartState::Missing.details()),
+                format!("  - {missing:?}"),
+                format!("  - {missing_two:?}"),
+                format!("[OK] 1 directory - {}", PartState::Valid.details()),
+                format!("  > {dir:?}"),
+                format!("[EMPTY] 1 directory - {}", PartState::EmptyDir.details()),
+                format!("  - {empty:?}"),
+            ],
+            lines[3..]
+        );
+    }
+
     #[test]
     fn check_parts_are_dirs() {
         let tmp_dir = tempfile::tempdir().unwrap();
@@ -182,7 +359,7 @@ mod tests {
         std::fs::write(&expected, "lol").unwrap();
         let program = Which {
             program: name,
-            path_env: Some(vec![expected.as_os_str(), dir.as_os_str()].join(&OsString::from(":"))),
+            path_env: Some([expected.as_os_str(), dir.as_os_str()].join(&OsString::from(":"))),
             ..Which::default()
         }
         .diagnose()
@@ -198,6 +375,101 @@ mod tests {
             .any(|p| p.state == PartState::NotDir));
     }
 
+    #[test]
+    fn check_file_part_suggests_parent_dir() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("node");
+        let name = OsString::from(file.file_name().unwrap());
+
+        std::fs::write(&file, "contents").unwrap();
+        make_executable(&file);
+
+        let program = Which {
+            program: name,
+            path_env: Some(file.as_os_str().into()),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        let part = program.path_parts.first().unwrap();
+        assert_eq!(PartState::NotDir, part.state);
+        assert_eq!(Some(PartHint::ParentDir(dir.to_path_buf())), part.hint);
+        assert!(
+            format!("{program}").contains(&format!("Help: Did you mean the directory {dir:?}?"))
+        );
+    }
+
+    #[test]
+    fn check_missing_part_suggests_different_case() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let actual = tmp_dir.path().join("opt").join("mytool").join("bin");
+        let file = actual.join("mytool");
+        let name = OsString::from(file.file_name().unwrap());
+
+        std::fs::create_dir_all(&actual).unwrap();
+        std::fs::write(&file, "contents").unwrap();
+        make_executable(&file);
+
+        let configured = tmp_dir.path().join("opt").join("MyTool").join("bin");
+        let program = Which {
+            program: name,
+            path_env: Some(configured.as_os_str().into()),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        let part = program.path_parts.first().unwrap();
+        assert_eq!(PartState::Missing, part.state);
+        assert_eq!(Some(PartHint::CaseMismatch(actual.clone())), part.hint);
+        assert!(format!("{program}").contains(&format!(
+            "Help: Directory not found, did you mean {actual:?}?"
+        )));
+    }
+
+    #[test]
+    fn check_missing_part_with_unsearchable_ancestor() {
+        // Restore search permission even if an assertion fails, so the
+        // temp dir can be cleaned up
+        struct Unlock<'a>(&'a Path);
+        impl Drop for Unlock<'_> {
+            fn drop(&mut self) {
+                let _ = std::fs::set_permissions(self.0, std::fs::Permissions::from_mode(0o755));
+            }
+        }
+
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let locked = tmp_dir.path().join("locked");
+        let bin = locked.join("tool").join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::fs::write(bin.join("lol"), "contents").unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o600)).unwrap();
+        let _unlock = Unlock(&locked);
+
+        let program = Which {
+            program: OsString::from("lol"),
+            path_env: Some(bin.as_os_str().into()),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        let part = program.path_parts.first().unwrap();
+        // Privileged users such as root ignore search permissions
+        if std::fs::symlink_metadata(&bin).is_ok() {
+            assert_eq!(PartState::Valid, part.state);
+            assert_eq!(None, part.hint);
+            return;
+        }
+        assert_eq!(PartState::Missing, part.state);
+        assert_eq!(Some(PartHint::Inaccessible(locked.clone())), part.hint);
+        assert!(format!("{program}").contains(&format!(
+            "Help: PATH directory is inaccessible because {locked:?} lacks search permission"
+        )));
+    }
+
     #[test]
     fn check_path_parts_exist() {
         let tmp_dir = tempfile::tempdir().unwrap();
@@ -208,7 +480,7 @@ mod tests {
 
         let program = Which {
             program: name,
-            path_env: Some(vec![expected.as_os_str(), dir.as_os_str()].join(&OsString::from(":"))),
+            path_env: Some([expected.as_os_str(), dir.as_os_str()].join(&OsString::from(":"))),
             ..Which::default()
         }
         .diagnose()
@@ -225,29 +497,1223 @@ mod tests {
     }
 
     #[test]
-    fn check_suggested_spelling() {
+    fn check_duplicate_path_parts() {
         let tmp_dir = tempfile::tempdir().unwrap();
         let dir = tmp_dir.path();
         let file = dir.join("lol");
         let name = OsString::from(file.file_name().unwrap());
 
-        let actual = dir.join("rofl");
-        std::fs::write(&actual, "contents").unwrap();
-        make_executable(&actual);
+        std::fs::write(&file, "contents").unwrap();
+        make_executable(&file);
+
+        let program = Which {
+            program: name,
+            path_env: Some([dir.as_os_str(), dir.as_os_str()].join(&OsString::from(":"))),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        assert_eq!(1, program.duplicate_path_parts().len());
+    }
+
+    #[test]
+    fn check_under_sudo() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("lol");
+        let name = OsString::from(file.file_name().unwrap());
+
+        std::fs::write(&file, "contents").unwrap();
+        make_executable(&file);
+
+        let tmp_dir_two = tempfile::tempdir().unwrap();
+        let secure_path = tmp_dir_two.path().as_os_str().to_os_string();
 
         let program = Which {
             program: name,
             path_env: Some(dir.as_os_str().into()),
+            sudo: Some(SecurePath::Explicit(secure_path.clone())),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        assert_eq!(1, program.found_files.len());
+        match program.sudo.clone().unwrap() {
+            sudo::UnderSudo::Diagnosed {
+                secure_path: actual,
+                program,
+            } => {
+                assert_eq!(secure_path, actual);
+                assert!(program.found_files.is_empty());
+            }
+            sudo::UnderSudo::Unknown(note) => panic!("Expected a diagnosis, got: {note}"),
+        }
+        assert!(format!("{program}").contains("Diagnosis under sudo:\n  Program \"lol\" not found"));
+    }
+
+    #[test]
+    fn check_unreadable_sudoers() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let sudoers = tmp_dir.path().join("sudoers");
+
+        let program = Which {
+            program: OsString::from("lol"),
+            path_env: Some(tmp_dir.path().as_os_str().into()),
+            sudo: Some(SecurePath::Sudoers(sudoers)),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        assert!(matches!(program.sudo, Some(sudo::UnderSudo::Unknown(_))));
+    }
+
+    #[test]
+    fn check_problems() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("lol");
+        let name = OsString::from(file.file_name().unwrap());
+        let missing = dir.join("nope");
+
+        std::fs::write(&file, "contents").unwrap();
+        make_executable(&file);
+
+        let program = Which {
+            program: name.clone(),
+            path_env: Some(dir.as_os_str().into()),
             ..Which::default()
         }
         .diagnose()
         .unwrap();
+        assert_eq!(Vec::<Problem>::new(), program.problems());
 
+        let program = Which {
+            program: OsString::from("rofl"),
+            path_env: Some([missing.as_os_str(), dir.as_os_str()].join(&OsString::from(":"))),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
         assert_eq!(
-            program.suggested.clone().unwrap(),
-            vec![actual.file_name().unwrap()]
+            vec![
+                Problem::NotFound,
+                Problem::InvalidPathPart(missing, PartState::Missing)
+            ],
+            program.problems()
         );
+    }
 
-        assert_eq!(program.name, file.file_name().unwrap());
+    #[test]
+    fn check_subcommand_suggestion() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("git-xyz");
+        std::fs::write(&file, "contents").unwrap();
+        make_executable(&file);
+
+        let which = Which {
+            program: OsString::from("xyz"),
+            path_env: Some(dir.as_os_str().into()),
+            subcommand_separator: Some("-".to_string()),
+            ..Which::default()
+        };
+        let program = which.diagnose().unwrap();
+
+        assert_eq!(
+            vec![suggest::Subcommand {
+                command: OsString::from("git"),
+                program: OsString::from("xyz"),
+                path: file
+            }],
+            program.subcommands
+        );
+        assert!(format!("{program}")
+            .contains(r#"Info: "xyz" may be a subcommand. Found "git-xyz", try `git xyz`"#));
+
+        let program = Which {
+            subcommand_separator: Some("_".to_string()),
+            ..which
+        }
+        .diagnose()
+        .unwrap();
+        assert!(program.subcommands.is_empty());
+    }
+
+    #[test]
+    fn check_dir_cache_is_used() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("lol");
+        let name = OsString::from(file.file_name().unwrap());
+
+        std::fs::write(&file, "contents").unwrap();
+        make_executable(&file);
+
+        // "rofl" is only in the cache, not on disk
+        let mut cache = HashMap::new();
+        cache.insert(
+            dir.to_path_buf(),
+            vec![name.clone(), OsString::from("rofl")],
+        );
+
+        let program = Which {
+            program: name,
+            path_env: Some(dir.as_os_str().into()),
+            ..Which::default()
+        }
+        .diagnose_with_cache(&mut cache)
+        .unwrap();
+
+        assert_eq!(1, program.found_files.len());
+        assert!(program.suggested.unwrap().contains(&OsString::from("rofl")));
+    }
+
+    #[test]
+    fn check_dir_cache_is_filled() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("lol");
+        let name = OsString::from(file.file_name().unwrap());
+
+        std::fs::write(&file, "contents").unwrap();
+        make_executable(&file);
+
+        let which = Which {
+            program: name.clone(),
+            path_env: Some(dir.as_os_str().into()),
+            ..Which::default()
+        };
+        let mut cache = HashMap::new();
+        which.diagnose_with_cache(&mut cache).unwrap();
+        assert_eq!(Some(&vec![name.clone()]), cache.get(dir));
+
+        // Not seen until the cached entry is removed
+        let rofl = dir.join("rofl");
+        std::fs::write(&rofl, "contents").unwrap();
+        make_executable(&rofl);
+        let program = which.diagnose_with_cache(&mut cache).unwrap();
+        assert_eq!(Some(&vec![name]), cache.get(dir));
+        assert_eq!(None, program.suggested);
+    }
+
+    #[test]
+    fn check_unset_path() {
+        let program = Which {
+            program: OsString::from("lol"),
+            path_env: None,
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        assert!(program.path_parts.is_empty());
+        assert_eq!(
+            vec![Problem::NotFound, Problem::UnsetPath],
+            program.problems()
+        );
+        assert!(format!("{program}").contains(
+            "Warning: PATH is not set in this process's environment, which is common for services launched outside a login shell"
+        ));
+    }
+
+    #[test]
+    fn check_allowed_dirs() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("lol");
+        let name = OsString::from(file.file_name().unwrap());
+        let tmp_dir_two = tempfile::tempdir().unwrap();
+        let allowed = tmp_dir_two.path();
+
+        std::fs::write(&file, "contents").unwrap();
+        make_executable(&file);
+
+        let which = Which {
+            program: name,
+            path_env: Some([allowed.as_os_str(), dir.as_os_str()].join(&OsString::from(":"))),
+            allowed_dirs: Some(vec![allowed.to_path_buf()]),
+            ..Which::default()
+        };
+        let program = which.diagnose().unwrap();
+
+        assert!(program
+            .problems()
+            .contains(&Problem::ResolvedFromUnapprovedDir(dir.to_path_buf())));
+        assert!(format!("{program}").contains("which is not one of the allowed directories"));
+
+        let program = Which {
+            allowed_dirs: Some(vec![dir.to_path_buf()]),
+            ..which
+        }
+        .diagnose()
+        .unwrap();
+        assert_eq!(None, program.unapproved_dir);
+    }
+
+    #[test]
+    fn check_suggest_total_file_limit() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let tmp_dir_two = tempfile::tempdir().unwrap();
+        let dir_two = tmp_dir_two.path();
+        for (dir, names) in [(dir, ["lola", "lolb"]), (dir_two, ["lolc", "lold"])] {
+            for name in names {
+                let file = dir.join(name);
+                std::fs::write(&file, "contents").unwrap();
+                make_executable(&file);
+            }
+        }
+
+        let program = Which {
+            program: OsString::from("lol"),
+            path_env: Some([dir.as_os_str(), dir_two.as_os_str()].join(&OsString::from(":"))),
+            guess_limit: 10,
+            suggest_total_file_limit: Some(3),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        assert_eq!(Some(3), program.suggest_truncated_at);
+        assert_eq!(3, program.suggested.clone().unwrap().len());
+        assert!(format!("{program}").contains("Info: Suggestion scan truncated after 3 files"));
+    }
+
+    #[test]
+    fn check_suggested_spelling() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("lol");
+        let name = OsString::from(file.file_name().unwrap());
+
+        let actual = dir.join("rofl");
+        std::fs::write(&actual, "contents").unwrap();
+        make_executable(&actual);
+
+        let program = Which {
+            program: name,
+            path_env: Some(dir.as_os_str().into()),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        assert_eq!(
+            program.suggested.clone().unwrap(),
+            vec![actual.file_name().unwrap()]
+        );
+
+        assert_eq!(program.name, file.file_name().unwrap());
+    }
+
+    #[test]
+    fn check_closest_suggestion_edits() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let actual = dir.join("rofl");
+        std::fs::write(&actual, "contents").unwrap();
+        make_executable(&actual);
+
+        let which = Which {
+            program: OsString::from("lol"),
+            path_env: Some(dir.as_os_str().into()),
+            ..Which::default()
+        };
+        let program = which.diagnose().unwrap();
+        assert!(format!("{program}").contains(
+            "Help: You wrote `lol`, the closest match is `rofl`: replace 'l' with 'r' at position 0, then insert 'f' at position 2"
+        ));
+
+        let program = Which {
+            program: OsString::from("rolf"),
+            ..which
+        }
+        .diagnose()
+        .unwrap();
+        assert!(format!("{program}").contains(
+            "Help: You wrote `rolf`, the closest match is `rofl`: swap 'l' and 'f' at position 2"
+        ));
+    }
+
+    #[test]
+    fn check_stale_winner() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let old_dir = tmp_dir.path();
+        let tmp_dir_two = tempfile::tempdir().unwrap();
+        let new_dir = tmp_dir_two.path();
+        let old = old_dir.join("lol");
+        let new = new_dir.join("lol");
+        for file in [&old, &new] {
+            std::fs::write(file, "contents").unwrap();
+            make_executable(file);
+        }
+        let week_ago =
+            std::time::SystemTime::now() - std::time::Duration::from_secs(7 * 24 * 60 * 60);
+        filetime::set_file_mtime(&old, filetime::FileTime::from_system_time(week_ago)).unwrap();
+
+        let which = Which {
+            program: OsString::from("lol"),
+            path_env: Some([old_dir.as_os_str(), new_dir.as_os_str()].join(&OsString::from(":"))),
+            check_staleness: true,
+            ..Which::default()
+        };
+        let program = which.diagnose().unwrap();
+
+        assert_eq!(Some(new.clone()), program.newer_shadowed);
+        assert!(program
+            .problems()
+            .contains(&Problem::OlderThanShadowed(old.clone(), new.clone())));
+        assert!(format!("{program}").contains("is older than another copy found later on the PATH"));
+
+        let program = Which {
+            check_staleness: false,
+            ..which.clone()
+        }
+        .diagnose()
+        .unwrap();
+        assert_eq!(None, program.newer_shadowed);
+
+        // A newer winner is not stale
+        let program = Which {
+            path_env: Some([new_dir.as_os_str(), old_dir.as_os_str()].join(&OsString::from(":"))),
+            ..which
+        }
+        .diagnose()
+        .unwrap();
+        assert_eq!(None, program.newer_shadowed);
+    }
+
+    #[test]
+    fn check_explicit_path_not_on_path() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("lol");
+        let tmp_dir_two = tempfile::tempdir().unwrap();
+        let path_dir = tmp_dir_two.path();
+
+        std::fs::write(&file, "contents").unwrap();
+        make_executable(&file);
+
+        let which = Which {
+            program: file.clone().into_os_string(),
+            path_env: Some(path_dir.as_os_str().into()),
+            ..Which::default()
+        };
+        let program = which.diagnose().unwrap();
+
+        assert_eq!(
+            Some(file.clone()),
+            program.executable().map(|p| p.path.clone())
+        );
+        assert_eq!(Some(file.clone()), which.which().unwrap());
+        let explicit = program.explicit.clone().unwrap();
+        assert_eq!(dir, explicit.dir.absolute);
+        assert_eq!(PartState::Valid, explicit.dir.state);
+        assert!(!explicit.on_path);
+        assert_eq!(1, program.path_parts.len());
+        assert!(format!("{program}").contains(&format!(
+            "Info: The directory {dir:?} is not on the PATH, so bare `lol` won't resolve, though the path {file:?} works"
+        )));
+
+        let program = Which {
+            path_env: Some(dir.as_os_str().into()),
+            ..which
+        }
+        .diagnose()
+        .unwrap();
+        assert!(program.explicit.unwrap().on_path);
+    }
+
+    #[test]
+    fn check_suggest_debug() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let executable = dir.join("lola");
+        let not_executable = dir.join("lolb");
+        std::fs::write(&executable, "contents").unwrap();
+        make_executable(&executable);
+        std::fs::write(&not_executable, "contents").unwrap();
+
+        let which = Which {
+            program: OsString::from("lol"),
+            path_env: Some(dir.as_os_str().into()),
+            suggest_debug: true,
+            suggest_executables_only: true,
+            ..Which::default()
+        };
+        let program = which.diagnose().unwrap();
+
+        assert_eq!(Some(vec![OsString::from("lola")]), program.suggested);
+        let dispositions = program
+            .suggestion_debug()
+            .iter()
+            .map(|candidate| (candidate.name.clone(), candidate.disposition))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (OsString::from("lola"), SuggestionDisposition::Suggested),
+                (OsString::from("lolb"), SuggestionDisposition::NotExecutable),
+            ],
+            dispositions
+        );
+        assert!(format!("{program}").contains(r#"0.750 "lolb" - not a valid executable"#));
+
+        let program = Which {
+            suggest_debug: false,
+            ..which
+        }
+        .diagnose()
+        .unwrap();
+        assert!(program.suggestion_debug().is_empty());
+    }
+
+    #[test]
+    fn check_quoted_path() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("lol");
+        let tmp_dir_two = tempfile::tempdir().unwrap();
+        let dir_two = tmp_dir_two.path();
+
+        std::fs::write(&file, "contents").unwrap();
+        make_executable(&file);
+
+        let mut path_env = OsString::from("\"");
+        path_env.push([dir.as_os_str(), dir_two.as_os_str()].join(&OsString::from(":")));
+        path_env.push("\"");
+
+        let which = Which {
+            program: OsString::from("lol"),
+            path_env: Some(path_env),
+            ..Which::default()
+        };
+        let program = which.diagnose().unwrap();
+
+        assert!(program.problems().contains(&Problem::QuotedPath));
+        assert!(program.problems().contains(&Problem::NotFound));
+        assert_eq!(PartState::Missing, program.path_parts[0].state);
+        assert!(format!("{program}").contains("The PATH is wrapped in quotes"));
+
+        let program = Which {
+            unquote_path: true,
+            ..which
+        }
+        .diagnose()
+        .unwrap();
+        assert!(program.problems().contains(&Problem::QuotedPath));
+        assert_eq!(
+            Some(file),
+            program.executable().map(|found| found.path.clone())
+        );
+        assert!(format!("{program}").contains("Info: Diagnosing the PATH with the quotes removed"));
+    }
+
+    #[test]
+    fn check_invisible_characters() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("lol");
+
+        std::fs::write(&file, "contents").unwrap();
+        make_executable(&file);
+
+        let program = Which {
+            program: OsString::from("l\u{200B}ol"),
+            path_env: Some(dir.as_os_str().into()),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        assert!(program
+            .problems()
+            .contains(&Problem::InvisibleCharacters(vec!['\u{200B}'])));
+        assert!(
+            format!("{program}").contains("Warning: Program contains invisible characters: U+200B")
+        );
+    }
+
+    #[test]
+    fn check_diagnose_parallel_programs() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let tmp_dir_two = tempfile::tempdir().unwrap();
+        let dir_two = tmp_dir_two.path();
+        for file in [dir.join("lol"), dir.join("rofl"), dir_two.join("lmao")] {
+            std::fs::write(&file, "contents").unwrap();
+            make_executable(&file);
+        }
+
+        let which = Which {
+            path_env: Some([dir.as_os_str(), dir_two.as_os_str()].join(&OsString::from(":"))),
+            ..Which::default()
+        };
+        let names = ["lmao", "lol", "missing", "rofl"];
+        let reads = std::sync::Mutex::new(Vec::new());
+        let programs = which
+            .diagnose_parallel_programs_with(&names, |dir| {
+                reads.lock().unwrap().push(dir.to_path_buf());
+                listing::read_dir(dir)
+            })
+            .unwrap();
+
+        let mut reads = reads.into_inner().unwrap();
+        reads.sort();
+        let mut expected = vec![dir.to_path_buf(), dir_two.to_path_buf()];
+        expected.sort();
+        assert_eq!(expected, reads);
+
+        assert_eq!(
+            vec![
+                Some(dir_two.join("lmao")),
+                Some(dir.join("lol")),
+                None,
+                Some(dir.join("rofl"))
+            ],
+            programs
+                .iter()
+                .map(|program| program.executable().map(|found| found.path.clone()))
+                .collect::<Vec<_>>()
+        );
+
+        for (name, program) in names.iter().zip(&programs) {
+            let sequential = Which {
+                program: OsString::from(name),
+                ..which.clone()
+            }
+            .diagnose()
+            .unwrap();
+            assert_eq!(sequential.to_string(), program.to_string());
+        }
+
+        for _ in 0..5 {
+            let again = which.diagnose_parallel_programs(&names).unwrap();
+            assert_eq!(
+                programs.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                again.iter().map(ToString::to_string).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn check_prefixed() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        for name in ["kubectl", "kubeadm", "kub", "minikube"] {
+            let file = dir.join(name);
+            std::fs::write(&file, "contents").unwrap();
+            make_executable(&file);
+        }
+        std::fs::write(dir.join("kubelet"), "contents").unwrap();
+
+        let program = Which {
+            program: OsString::from("kube"),
+            path_env: Some(dir.as_os_str().into()),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        assert_eq!(
+            vec![dir.join("kubeadm"), dir.join("kubectl")],
+            program.prefixed
+        );
+        assert!(format!("{program}").contains(
+            "Info: Commands starting with \"kube\", the name may be missing a suffix:\n      \"kubeadm\", \"kubectl\"\n"
+        ));
+
+        let program = Which {
+            program: OsString::from("kubectl"),
+            path_env: Some(dir.as_os_str().into()),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+        assert!(program.prefixed.is_empty());
+    }
+
+    #[test]
+    fn check_show_permissions() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("lol");
+        let tmp_dir_two = tempfile::tempdir().unwrap();
+        let dir_two = tmp_dir_two.path();
+        let file_two = dir_two.join("lol");
+        std::fs::write(&file, "contents").unwrap();
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o644)).unwrap();
+        std::fs::write(&file_two, "contents").unwrap();
+        make_executable(&file_two);
+
+        let which = Which {
+            program: OsString::from("lol"),
+            path_env: Some([dir.as_os_str(), dir_two.as_os_str()].join(&OsString::from(":"))),
+            show_permissions: true,
+            ..Which::default()
+        };
+        let program = which.diagnose().unwrap();
+
+        let user = uzers::get_current_username().unwrap();
+        let group = uzers::get_current_groupname().unwrap();
+        let owner = format!("{}:{}", user.to_string_lossy(), group.to_string_lossy());
+        assert_eq!(0o644, program.permissions[&file].mode);
+        assert_eq!(owner, program.permissions[&file].owner);
+        assert!(format!("{program}").contains(&format!(
+            "- [NOT EXE] {file:?} (mode 0644, owned by {owner})"
+        )));
+
+        let program = Which {
+            show_permissions: false,
+            ..which
+        }
+        .diagnose()
+        .unwrap();
+        assert!(program.permissions.is_empty());
+        assert!(!format!("{program}").contains("(mode 0644"));
+    }
+
+    #[test]
+    fn check_separator_variants() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        for name in ["docker-compose", "foo-bar", "foobaz"] {
+            let file = dir.join(name);
+            std::fs::write(&file, "contents").unwrap();
+            make_executable(&file);
+        }
+        let diagnose = |program: &str| {
+            Which {
+                program: OsString::from(program),
+                path_env: Some(dir.as_os_str().into()),
+                ..Which::default()
+            }
+            .diagnose()
+            .unwrap()
+        };
+
+        let program = diagnose("docker_compose");
+        assert_eq!(vec![dir.join("docker-compose")], program.separator_variants);
+        assert!(format!("{program}").contains("Info: Found \"docker-compose\" at"));
+        assert!(format!("{program}").contains("you may have typed the separator differently"));
+
+        assert_eq!(
+            vec![dir.join("foo-bar")],
+            diagnose("foobar").separator_variants
+        );
+        assert_eq!(
+            vec![dir.join("foobaz")],
+            diagnose("foo_baz").separator_variants
+        );
+        assert!(diagnose("foo-bar").separator_variants.is_empty());
+    }
+
+    #[test]
+    fn check_ignore_empty_dirs() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let ignored = tmp_dir.path();
+        let tmp_dir_two = tempfile::tempdir().unwrap();
+        let empty = tmp_dir_two.path();
+
+        let program = Which {
+            program: OsString::from("lol"),
+            path_env: Some([ignored.as_os_str(), empty.as_os_str()].join(&OsString::from(":"))),
+            ignore_empty_dirs: vec![ignored.to_path_buf()],
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        assert_eq!(PartState::EmptyDir, program.path_parts[0].state);
+        assert!(program.path_parts[0].expected_empty);
+        assert_eq!(PartState::EmptyDir, program.path_parts[1].state);
+        assert!(!program.path_parts[1].expected_empty);
+        assert_eq!(
+            vec![
+                Problem::NotFound,
+                Problem::InvalidPathPart(empty.to_path_buf(), PartState::EmptyDir)
+            ],
+            program.problems()
+        );
+    }
+
+    #[test]
+    fn check_remediation_chmod() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("lol");
+        std::fs::write(&file, "contents").unwrap();
+
+        let program = Which {
+            program: OsString::from("lol"),
+            path_env: Some(dir.as_os_str().into()),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        let expected = format!("chmod +x '{}'", file.display());
+        assert_eq!(vec![expected.clone()], program.remediation_commands());
+        assert!(format!("{program}").contains(&format!("To fix, run:\n  {expected}\n")));
+
+        make_executable(&file);
+        let program = Which {
+            program: OsString::from("lol"),
+            path_env: Some(dir.as_os_str().into()),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+        assert!(program.remediation_commands().is_empty());
+    }
+
+    #[test]
+    fn check_remediation_add_to_path() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("lol");
+        let tmp_dir_two = tempfile::tempdir().unwrap();
+
+        std::fs::write(&file, "contents").unwrap();
+        make_executable(&file);
+
+        let program = Which {
+            program: file.into_os_string(),
+            path_env: Some(tmp_dir_two.path().as_os_str().into()),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        assert_eq!(
+            vec![format!(r#"export PATH="$PATH":'{}'"#, dir.display())],
+            program.remediation_commands()
+        );
+    }
+
+    #[test]
+    fn check_first_problem() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let missing = dir.join("nope");
+        let file = dir.join("lola");
+        std::fs::write(&file, "contents").unwrap();
+        make_executable(&file);
+
+        let which = Which {
+            program: OsString::from("lol"),
+            path_env: Some([missing.as_os_str(), dir.as_os_str()].join(&OsString::from(":"))),
+            suggest_debug: true,
+            ..Which::default()
+        };
+        let spelling_calls = || suggest::SPELLING_CALLS.with(std::cell::Cell::get);
+        let before = spelling_calls();
+        assert_eq!(Some(Problem::NotFound), which.first_problem().unwrap());
+        assert_eq!(before, spelling_calls());
+
+        let program = which.diagnose().unwrap();
+        assert_eq!(before + 1, spelling_calls());
+        assert!(!program.suggestion_debug.is_empty());
+        assert_eq!(
+            program.problems().into_iter().next(),
+            which.first_problem().unwrap()
+        );
+
+        let which = Which {
+            program: OsString::from("lola"),
+            ..which
+        };
+        assert_eq!(
+            Some(Problem::InvalidPathPart(
+                missing.clone(),
+                PartState::Missing
+            )),
+            which.first_problem().unwrap()
+        );
+        assert_eq!(
+            which.diagnose().unwrap().problems().into_iter().next(),
+            which.first_problem().unwrap()
+        );
+
+        // Files come before PATH parts in the same severity
+        let tmp_dir_two = tempfile::tempdir().unwrap();
+        let dir_two = tmp_dir_two.path();
+        let shadowed = dir_two.join("lola");
+        std::fs::write(&shadowed, "contents").unwrap();
+        make_executable(&shadowed);
+        let shadowing = Which {
+            path_env: Some(
+                [missing.as_os_str(), dir.as_os_str(), dir_two.as_os_str()]
+                    .join(&OsString::from(":")),
+            ),
+            ..which.clone()
+        };
+        assert_eq!(
+            Some(Problem::Shadowed(shadowed)),
+            shadowing.first_problem().unwrap()
+        );
+        assert_eq!(
+            shadowing.diagnose().unwrap().problems().into_iter().next(),
+            shadowing.first_problem().unwrap()
+        );
+
+        let which = Which {
+            path_env: Some(dir.as_os_str().into()),
+            ..which
+        };
+        assert_eq!(None, which.first_problem().unwrap());
+    }
+
+    #[test]
+    fn check_local_bin() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let project = tmp_dir.path();
+        let bin = project.join("node_modules").join(".bin");
+        let cwd = project.join("src").join("nested");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::fs::create_dir_all(&cwd).unwrap();
+        let file = bin.join("lol");
+        std::fs::write(&file, "contents").unwrap();
+        make_executable(&file);
+        let tmp_dir_two = tempfile::tempdir().unwrap();
+
+        let which = Which {
+            program: OsString::from("lol"),
+            cwd: Some(cwd),
+            path_env: Some(tmp_dir_two.path().as_os_str().into()),
+            ..Which::default()
+        };
+        let program = which.diagnose().unwrap();
+
+        assert_eq!(Some(file), program.local_bin);
+        assert!(format!("{program}").contains(&format!(
+            "Help: Run it via `npx lol` or add {bin:?} to the PATH"
+        )));
+
+        let program = Which {
+            local_bin_dirs: vec![PathBuf::from("bin")],
+            ..which
+        }
+        .diagnose()
+        .unwrap();
+        assert_eq!(None, program.local_bin);
+    }
+
+    #[test]
+    fn check_symlink_chain_longer_than_max_hops() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let bin = dir.join("bin");
+        std::fs::create_dir(&bin).unwrap();
+        let real = dir.join("real");
+        std::fs::write(&real, "contents").unwrap();
+        make_executable(&real);
+
+        // lol -> hop_4 -> hop_3 -> hop_2 -> hop_1 -> real
+        let mut target = real;
+        for hop in 1..=4 {
+            let link = dir.join(format!("hop_{hop}"));
+            std::os::unix::fs::symlink(&target, &link).unwrap();
+            target = link;
+        }
+        let file = bin.join("lol");
+        std::os::unix::fs::symlink(&target, &file).unwrap();
+
+        let which = Which {
+            program: OsString::from("lol"),
+            path_env: Some(bin.as_os_str().into()),
+            max_symlink_hops: Some(3),
+            ..Which::default()
+        };
+        let program = which.diagnose().unwrap();
+        assert_eq!(
+            vec![PathWithState {
+                path: file.clone(),
+                state: FileState::TooManySymlinkHops,
+            }],
+            program.found_files
+        );
+        assert_eq!(Some(&Problem::NotFound), program.problems().first());
+        assert_eq!(None, which.which().unwrap());
+
+        let program = Which {
+            max_symlink_hops: Some(5),
+            ..which
+        }
+        .diagnose()
+        .unwrap();
+        assert_eq!(Some(&file), program.executable().map(|p| &p.path));
+    }
+
+    #[test]
+    fn check_symlink_chain_state_within_max_hops() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let not_executable = dir.join("not_executable");
+        std::fs::write(&not_executable, "contents").unwrap();
+        let link = |name: &str, target: &Path| {
+            let hop = dir.join(format!("{name}_hop"));
+            std::os::unix::fs::symlink(target, &hop).unwrap();
+            let link = dir.join(name);
+            std::os::unix::fs::symlink(&hop, &link).unwrap();
+            link
+        };
+
+        assert_eq!(
+            FileState::IsDir,
+            file_state::bounded_file_state(&link("dir", dir), Some(3))
+        );
+        assert_eq!(
+            FileState::NotExecutable,
+            file_state::bounded_file_state(&link("file", &not_executable), Some(3))
+        );
+        assert_eq!(
+            FileState::BadSymlink,
+            file_state::bounded_file_state(&link("missing", &dir.join("nope")), Some(3))
+        );
+    }
+
+    #[test]
+    fn check_precedence_explanation() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dirs = ["a", "b", "c"].map(|name| tmp_dir.path().join(name));
+        for dir in &dirs {
+            std::fs::create_dir(dir).unwrap();
+            std::fs::write(dir.join("lol"), "contents").unwrap();
+        }
+        make_executable(&dirs[1].join("lol"));
+        make_executable(&dirs[2].join("lol"));
+
+        let program = Which {
+            program: OsString::from("lol"),
+            path_env: Some(std::env::join_paths(&dirs).unwrap()),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        let [a, b, c] = dirs.map(|dir| dir.join("lol"));
+        assert_eq!(
+            format!(
+                "The PATH is searched from the first directory to the last and the first valid executable wins. \
+                {b:?} runs because it's the first valid match in PATH order; \
+                {a:?} comes earlier but can't run, so it's skipped; \
+                {c:?} is never reached."
+            ),
+            program.precedence_explanation()
+        );
+    }
+
+    #[test]
+    fn check_more_suggestions() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        for name in ["lol1", "lol22", "lol333", "lol4444", "lol55555"] {
+            let file = dir.join(name);
+            std::fs::write(&file, "contents").unwrap();
+            make_executable(&file);
+        }
+
+        let program = Which {
+            program: OsString::from("lol"),
+            path_env: Some(dir.as_os_str().into()),
+            guess_limit: 2,
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        let original = program
+            .suggestions_with_paths()
+            .iter()
+            .map(|path| path.file_name().unwrap().to_os_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![OsString::from("lol1"), OsString::from("lol22")],
+            original
+        );
+
+        let more = program.more_suggestions(2);
+        assert_eq!(4, more.len());
+        assert_eq!(original, more[..2]);
+        assert!(original.iter().all(|name| more.contains(name)));
+    }
+
+    #[test]
+    fn check_duplicate_path_parts_differing_by_slashes_and_dots() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path().join("bin");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("lol"), "contents").unwrap();
+
+        let originals = [
+            dir.clone(),
+            PathBuf::from(format!("{}/", dir.display())),
+            tmp_dir.path().join(".").join("bin"),
+        ];
+        let program = Which {
+            program: OsString::from("lol"),
+            path_env: Some(
+                originals
+                    .iter()
+                    .map(|path| path.as_os_str())
+                    .collect::<Vec<_>>()
+                    .join(&OsString::from(":")),
+            ),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        assert_eq!(
+            vec![Problem::DuplicatePathPart(originals.to_vec())],
+            program
+                .problems()
+                .into_iter()
+                .filter(|problem| matches!(problem, Problem::DuplicatePathPart(_)))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn check_render_actionable_first() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("lol");
+        std::fs::write(&file, "contents").unwrap();
+
+        let program = Which {
+            program: OsString::from("lol"),
+            path_env: Some(dir.as_os_str().into()),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        let rendered = program.render_actionable_first();
+        let mut lines = rendered.lines();
+        assert_eq!(Some("Error: Program not found"), lines.next());
+        assert_eq!(Some("To fix, run:"), lines.next());
+        assert_eq!(
+            Some(format!("  chmod +x '{}'", file.display()).as_str()),
+            lines.next()
+        );
+        assert!(format!("{program}").contains("To fix, run:"));
+        assert_eq!(1, rendered.matches("To fix, run:").count());
+    }
+
+    #[test]
+    fn check_suggest_executables_only() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let executable = dir.join("lola");
+        let not_executable = dir.join("lolb");
+        std::fs::write(&executable, "contents").unwrap();
+        make_executable(&executable);
+        std::fs::write(&not_executable, "contents").unwrap();
+
+        let which = Which {
+            program: OsString::from("lol"),
+            path_env: Some(dir.as_os_str().into()),
+            ..Which::default()
+        };
+        let program = which.diagnose().unwrap();
+        assert_eq!(
+            Some(vec![OsString::from("lola"), OsString::from("lolb")]),
+            program.suggested
+        );
+
+        let program = Which {
+            suggest_executables_only: true,
+            ..which
+        }
+        .diagnose()
+        .unwrap();
+        assert_eq!(Some(vec![OsString::from("lola")]), program.suggested);
+    }
+
+    #[test]
+    fn check_suggestions_with_paths() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let tmp_dir_two = tempfile::tempdir().unwrap();
+        let dir_two = tmp_dir_two.path();
+        let rofl = dir_two.join("rofl");
+        let lola = dir.join("lola");
+        for file in [&rofl, &lola] {
+            std::fs::write(file, "contents").unwrap();
+            make_executable(file);
+        }
+
+        let program = Which {
+            program: OsString::from("lol"),
+            path_env: Some([dir.as_os_str(), dir_two.as_os_str()].join(&OsString::from(":"))),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        assert_eq!(vec![lola, rofl], program.suggestions_with_paths());
+        assert!(program
+            .suggestions_with_paths()
+            .iter()
+            .all(|path| file_state(path) == FileState::Valid));
+
+        let program = Which {
+            program: OsString::from("lol"),
+            path_env: Some(dir.as_os_str().into()),
+            guess_limit: 0,
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+        assert!(program.suggestions_with_paths().is_empty());
+    }
+
+    #[test]
+    fn check_into_problems() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("lol");
+        let missing = dir.join("nope");
+        std::fs::write(&file, "contents").unwrap();
+        make_executable(&file);
+
+        let program = Which {
+            program: OsString::from("lol"),
+            path_env: Some(
+                [dir.as_os_str(), missing.as_os_str(), dir.as_os_str()].join(&OsString::from(":")),
+            ),
+            ..Which::default()
+        }
+        .diagnose()
+        .unwrap();
+
+        let problems = program.problems();
+        assert_eq!(
+            vec![
+                Problem::InvalidPathPart(missing, PartState::Missing),
+                Problem::DuplicatePathPart(vec![dir.to_path_buf(), dir.to_path_buf()]),
+            ],
+            problems
+        );
+        assert_eq!(problems, program.clone().into_problems());
+
+        // The missing part is moved into its problem, not cloned
+        let original = program.path_parts[1]
+            .original
+            .as_os_str()
+            .as_bytes()
+            .as_ptr();
+        match &Vec::<Problem>::from(program)[0] {
+            Problem::InvalidPathPart(path, _) => {
+                assert_eq!(original, path.as_os_str().as_bytes().as_ptr());
+            }
+            problem => panic!("Unexpected problem {problem:?}"),
+        }
     }
 }
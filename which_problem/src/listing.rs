@@ -0,0 +1,107 @@
+use crate::path_part::PathPart;
+use itertools::Itertools;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Names of the entries in directories, keyed by absolute directory path
+pub(crate) type DirCache = HashMap<PathBuf, Vec<OsString>>;
+
+/// Names of the entries in a PATH directory
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Listing {
+    pub(crate) dir: PathBuf,
+    pub(crate) names: Vec<OsString>,
+}
+
+/// Read the names of all entries in a directory
+///
+/// Returns None if the directory cannot be read.
+pub(crate) fn read_dir(path: &Path) -> Option<Vec<OsString>> {
+    std::fs::read_dir(path).ok().map(|read_dir| {
+        read_dir
+            .filter_map(std::result::Result::ok)
+            .map(|entry| entry.file_name())
+            .collect()
+    })
+}
+
+/// Read every directory that is not already in the cache, in parallel
+///
+/// Each directory is read at most once, even if it's listed more than once.
+/// Directories that cannot be read are not added.
+pub(crate) fn fill<'a, I: IntoIterator<Item = &'a Path>>(cache: &mut DirCache, dirs: I) {
+    fill_with(cache, dirs, read_dir);
+}
+
+/// Same as `fill`, but reads each directory with `read`
+pub(crate) fn fill_with<'a, I, R>(cache: &mut DirCache, dirs: I, read: R)
+where
+    I: IntoIterator<Item = &'a Path>,
+    R: Fn(&Path) -> Option<Vec<OsString>> + Sync,
+{
+    let misses = dirs
+        .into_iter()
+        .filter(|dir| !cache.contains_key(*dir))
+        .unique()
+        .collect::<Vec<_>>();
+
+    let read = misses
+        .par_iter()
+        .filter_map(|dir| read(dir).map(|names| (dir.to_path_buf(), names)))
+        .collect::<Vec<_>>();
+
+    cache.extend(read);
+}
+
+/// Listings for each PATH part found in the cache, in PATH order
+pub(crate) fn from_cache<'a, I: IntoIterator<Item = &'a PathPart>>(
+    cache: &DirCache,
+    parts: I,
+) -> Vec<Listing> {
+    parts
+        .into_iter()
+        .filter_map(|part| {
+            cache.get(&part.absolute).map(|names| Listing {
+                dir: part.absolute.clone(),
+                names: names.clone(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn fill_reads_each_missing_dir_once() {
+        let cached = PathBuf::from("/cached");
+        let missing = PathBuf::from("/missing");
+        let unreadable = PathBuf::from("/unreadable");
+
+        let mut cache = DirCache::new();
+        cache.insert(cached.clone(), vec![OsString::from("lol")]);
+
+        let reads = Mutex::new(Vec::new());
+        fill_with(
+            &mut cache,
+            [&cached, &missing, &missing, &unreadable]
+                .into_iter()
+                .map(PathBuf::as_path),
+            |dir| {
+                reads.lock().unwrap().push(dir.to_path_buf());
+                (dir == missing).then(|| vec![OsString::from("rofl")])
+            },
+        );
+
+        let mut reads = reads.into_inner().unwrap();
+        reads.sort();
+        assert_eq!(vec![missing.clone(), unreadable.clone()], reads);
+        assert_eq!(Some(&vec![OsString::from("lol")]), cache.get(&cached));
+        assert_eq!(Some(&vec![OsString::from("rofl")]), cache.get(&missing));
+        assert_eq!(None, cache.get(&unreadable));
+    }
+}
@@ -0,0 +1,99 @@
+use std::path::{Path, PathBuf};
+
+/// Produce a key for comparing two directories on the PATH
+///
+/// The result is intended for detecting when two PATH parts point at the same
+/// directory, it should not be shown to the user. Display the original
+/// path instead.
+///
+/// Trailing slashes, `.` segments, and repeated separators are removed, so
+/// `/usr/bin/` and `/usr/./bin` are the same directory as `/usr/bin`.
+///
+/// On Windows short (8.3) names such as `C:\PROGRA~1` are expanded to their
+/// long form and the result is case-folded, since the filesystem is
+/// case-insensitive.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let path = path.components().collect::<PathBuf>();
+
+    #[cfg(windows)]
+    {
+        let long = long_path(&path).unwrap_or(path);
+        PathBuf::from(long.to_string_lossy().to_lowercase())
+    }
+
+    #[cfg(not(windows))]
+    {
+        path
+    }
+}
+
+/// Expand a Windows short (8.3) path via `GetLongPathNameW`
+///
+/// Returns None if the path does not exist or cannot be expanded.
+#[cfg(windows)]
+fn long_path(path: &Path) -> Option<PathBuf> {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+    use windows_sys::Win32::Storage::FileSystem::GetLongPathNameW;
+
+    let wide = path
+        .as_os_str()
+        .encode_wide()
+        .chain(Some(0))
+        .collect::<Vec<u16>>();
+
+    // First call returns the buffer size needed, including the null terminator
+    let needed = unsafe { GetLongPathNameW(wide.as_ptr(), std::ptr::null_mut(), 0) };
+    if needed == 0 {
+        return None;
+    }
+
+    let mut buffer = vec![0u16; needed as usize];
+    // Second call returns the number of characters written, excluding the null terminator
+    let written = unsafe { GetLongPathNameW(wide.as_ptr(), buffer.as_mut_ptr(), needed) };
+    if written == 0 || written >= needed {
+        return None;
+    }
+    buffer.truncate(written as usize);
+
+    Some(PathBuf::from(OsString::from_wide(&buffer)))
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(unix)]
+    #[test]
+    fn cosmetic_differences_are_the_same_dir() {
+        use super::*;
+
+        for path in ["/usr/bin/", "/usr/./bin", "/usr//bin", "/usr/bin/."] {
+            assert_eq!(PathBuf::from("/usr/bin"), normalize(Path::new(path)));
+        }
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_short_and_long_names_are_the_same_dir() {
+        use super::*;
+        use std::ffi::OsString;
+        use std::os::windows::ffi::{OsStrExt, OsStringExt};
+        use windows_sys::Win32::Storage::FileSystem::GetShortPathNameW;
+
+        let long = PathBuf::from(std::env::var_os("ProgramFiles").unwrap());
+        let wide = long
+            .as_os_str()
+            .encode_wide()
+            .chain(Some(0))
+            .collect::<Vec<u16>>();
+        let mut buffer = vec![0u16; 1024];
+        let written = unsafe { GetShortPathNameW(wide.as_ptr(), buffer.as_mut_ptr(), 1024) };
+        assert!(written > 0);
+        buffer.truncate(written as usize);
+        let short = PathBuf::from(OsString::from_wide(&buffer));
+
+        let upper = PathBuf::from(long.to_string_lossy().to_uppercase());
+
+        assert_eq!(normalize(&long), normalize(&short));
+        assert_eq!(normalize(&long), normalize(&upper));
+    }
+}
@@ -0,0 +1,89 @@
+use crate::normalize::normalize;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// Directories added to or removed from a PATH relative to another PATH
+///
+/// Directories are compared after normalizing, the original spellings are kept
+/// in PATH order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct PathDiff {
+    /// In the diagnosed PATH, but not in the other PATH
+    pub(crate) added: Vec<PathBuf>,
+
+    /// In the other PATH, but not in the diagnosed PATH
+    pub(crate) removed: Vec<PathBuf>,
+}
+
+impl PathDiff {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Compare the `diagnosed` PATH against an `other` PATH, relative parts are relative to `cwd`
+///
+/// A PATH that is not set has no directories.
+pub(crate) fn diff(cwd: &Path, diagnosed: Option<&OsStr>, other: Option<&OsStr>) -> PathDiff {
+    let parts = |path_env: Option<&OsStr>| {
+        path_env
+            .into_iter()
+            .flat_map(std::env::split_paths)
+            .map(|part| (normalize(&cwd.join(&part)), part))
+            .collect::<Vec<_>>()
+    };
+    let diagnosed = parts(diagnosed);
+    let other = parts(other);
+
+    let only_in = |parts: &[(PathBuf, PathBuf)], other: &[(PathBuf, PathBuf)]| {
+        let other = other
+            .iter()
+            .map(|(normalized, _)| normalized)
+            .collect::<HashSet<_>>();
+        let mut seen = HashSet::new();
+        parts
+            .iter()
+            .filter(|(normalized, _)| !other.contains(normalized))
+            .filter(|(normalized, _)| seen.insert(normalized))
+            .map(|(_, original)| original.clone())
+            .collect::<Vec<_>>()
+    };
+
+    PathDiff {
+        added: only_in(&diagnosed, &other),
+        removed: only_in(&other, &diagnosed),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_against_stubbed_process_path() {
+        let cwd = PathBuf::from("/home/user");
+        let process = OsStr::new("/usr/local/bin:/usr/bin:/bin");
+        let custom = OsStr::new("/opt/ci/bin:/usr/bin:bin:/bin:/opt/ci/bin");
+
+        assert_eq!(
+            PathDiff {
+                added: vec![PathBuf::from("/opt/ci/bin"), PathBuf::from("bin")],
+                removed: vec![PathBuf::from("/usr/local/bin")],
+            },
+            diff(&cwd, Some(custom), Some(process))
+        );
+        assert!(diff(&cwd, Some(process), Some(process)).is_empty());
+        assert_eq!(
+            PathDiff {
+                added: Vec::new(),
+                removed: vec![
+                    PathBuf::from("/usr/local/bin"),
+                    PathBuf::from("/usr/bin"),
+                    PathBuf::from("/bin")
+                ],
+            },
+            diff(&cwd, None, Some(process))
+        );
+    }
+}
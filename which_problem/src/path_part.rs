@@ -1,6 +1,10 @@
+use crate::listing::{self, DirCache};
+use crate::normalize::normalize;
+use itertools::Itertools;
 use std::{
+    ffi::OsString,
     fmt::Display,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
 };
 
 /// Represents pieces of a PATH
@@ -26,7 +30,17 @@ pub(crate) struct PathPart {
     /// Original part of the PATH
     pub(crate) original: PathBuf,
 
-    relative: bool,
+    /// Comparison key used to detect parts that point at the same directory
+    pub(crate) normalized: PathBuf,
+
+    /// Help for fixing a part that is not valid
+    pub(crate) hint: Option<PartHint>,
+
+    /// True when the original part is a relative path
+    pub(crate) relative: bool,
+
+    /// True when the part is an empty directory listed in `Which::ignore_empty_dirs`
+    pub(crate) expected_empty: bool,
 }
 
 impl PartState {
@@ -43,6 +57,7 @@ impl PartState {
 }
 
 impl Display for PathPart {
+    #[allow(clippy::unnecessary_debug_formatting)]
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let state = &self.state;
         let path = &self.original;
@@ -63,8 +78,10 @@ impl Display for PathPart {
 }
 
 impl PathPart {
+    /// Entries of the directory are looked up in `cache`, when the
+    /// directory is not in the cache it's treated as empty.
     #[must_use]
-    pub(crate) fn new(cwd: &Path, original: &Path) -> Self {
+    pub(crate) fn new(cwd: &Path, original: &Path, cache: &DirCache) -> Self {
         let cwd = cwd.to_path_buf();
         let original = original.to_path_buf();
         let relative = original.is_relative();
@@ -74,19 +91,41 @@ impl PathPart {
             original.clone()
         };
 
-        let state = part_state(&absolute);
+        let state = part_state(&absolute, cache.get(&absolute));
+        let normalized = normalize(&absolute);
+        let hint = part_hint(&absolute, &state);
 
         Self {
             absolute,
             cwd,
             state,
             original,
+            normalized,
+            hint,
             relative,
+            expected_empty: false,
         }
     }
 }
 
+/// Groups of parts that point at the same directory, in PATH order
+pub(crate) fn duplicates(parts: &[PathPart]) -> Vec<Vec<&PathPart>> {
+    parts
+        .iter()
+        .into_group_map_by(|part| &part.normalized)
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .sorted_by_key(|group| {
+            parts
+                .iter()
+                .position(|part| part == group[0])
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PartState {
     /// No problems detected
     Valid,
@@ -112,18 +151,97 @@ impl Display for PartState {
     }
 }
 
-fn any_files_in_dir(path: &Path) -> bool {
-    if let Ok(read_dir) = std::fs::read_dir(path) {
-        read_dir.filter_map(std::result::Result::ok).any(|_| true)
-    } else {
-        false
+/// Help for fixing a PATH part that is not valid
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum PartHint {
+    /// The part is a file inside of a directory, i.e. `/usr/local/bin/node`
+    /// was added to the PATH instead of `/usr/local/bin`
+    ParentDir(PathBuf),
+
+    /// The part is missing, but a directory with different case exists
+    /// i.e. `/opt/MyTool/bin` was added to the PATH instead of `/opt/mytool/bin`
+    CaseMismatch(PathBuf),
+
+    /// The part might exist, but a directory above it lacks search (execute)
+    /// permission so it cannot be accessed
+    Inaccessible(PathBuf),
+}
+
+impl Display for PartHint {
+    #[allow(clippy::unnecessary_debug_formatting)]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PartHint::ParentDir(parent) => write!(f, "Did you mean the directory {parent:?}?"),
+            PartHint::CaseMismatch(path) => {
+                write!(f, "Directory not found, did you mean {path:?}?")
+            }
+            PartHint::Inaccessible(ancestor) => write!(
+                f,
+                "PATH directory is inaccessible because {ancestor:?} lacks search permission"
+            ),
+        }
     }
 }
 
-fn part_state(path: &Path) -> PartState {
+fn part_hint(path: &Path, state: &PartState) -> Option<PartHint> {
+    match state {
+        PartState::NotDir => path
+            .parent()
+            .filter(|parent| parent.is_dir())
+            .map(|parent| PartHint::ParentDir(parent.to_path_buf())),
+        PartState::Missing => unsearchable_ancestor(path)
+            .map(PartHint::Inaccessible)
+            .or_else(|| case_insensitive_dir(path).map(PartHint::CaseMismatch)),
+        PartState::Valid | PartState::EmptyDir => None,
+    }
+}
+
+/// Find the first directory above the path that does not allow searching it
+///
+/// Looking up an entry fails with permission denied when its parent
+/// directory is missing search (execute) permission.
+fn unsearchable_ancestor(path: &Path) -> Option<PathBuf> {
+    let mut ancestors = path.ancestors().collect::<Vec<_>>();
+    ancestors.reverse();
+    for ancestor in ancestors {
+        match std::fs::symlink_metadata(ancestor) {
+            Ok(_) => {}
+            Err(error) if error.kind() == std::io::ErrorKind::PermissionDenied => {
+                return ancestor.parent().map(Path::to_path_buf)
+            }
+            Err(_) => return None,
+        }
+    }
+    None
+}
+
+/// Find a directory matching the path when ignoring case
+///
+/// Each component that does not exist is matched case-insensitively against
+/// the entries of its parent. Returns None if there's no match, or if the
+/// path already exists as is.
+fn case_insensitive_dir(path: &Path) -> Option<PathBuf> {
+    let mut corrected = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) if !corrected.join(name).exists() => {
+                let lowercase = name.to_string_lossy().to_lowercase();
+                let actual = listing::read_dir(&corrected)?
+                    .into_iter()
+                    .find(|entry| entry.to_string_lossy().to_lowercase() == lowercase)?;
+                corrected.push(actual);
+            }
+            _ => corrected.push(component),
+        }
+    }
+
+    (corrected.is_dir() && corrected != path).then_some(corrected)
+}
+
+fn part_state(path: &Path, names: Option<&Vec<OsString>>) -> PartState {
     if path.exists() {
         if path.is_dir() {
-            if any_files_in_dir(path) {
+            if matches!(names, Some(names) if !names.is_empty()) {
                 PartState::Valid
             } else {
                 PartState::EmptyDir
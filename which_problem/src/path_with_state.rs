@@ -1,6 +1,6 @@
-use crate::file_state::{file_state, FileState};
+use crate::file_state::{bounded_file_state, FileState};
 use core::fmt::Display;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Represents a file on disk inside of a PATH directory
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -9,14 +9,61 @@ pub(crate) struct PathWithState {
     pub(crate) state: FileState,
 }
 
+/// Permission bits and owner of a file, captured with `Which::show_permissions` on unix
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Permissions {
+    /// Permission bits i.e. `0o644`
+    pub(crate) mode: u32,
+
+    /// Owner as `user:group` i.e. `root:staff`
+    pub(crate) owner: String,
+}
+
 impl PathWithState {
-    pub(crate) fn new(path: PathBuf) -> Self {
-        let state = file_state(&path);
+    /// Symlinks are followed one hop at a time when `max_hops` is set, see `Which::max_symlink_hops`
+    pub(crate) fn new(path: PathBuf, max_hops: Option<usize>) -> Self {
+        let state = bounded_file_state(&path, max_hops);
         PathWithState { path, state }
     }
 }
 
+impl Permissions {
+    /// The permission bits and owner of a file, following symlinks
+    ///
+    /// Returns None when the file cannot be read.
+    #[cfg(unix)]
+    pub(crate) fn of(path: &Path) -> Option<Self> {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        std::fs::metadata(path).ok().map(|metadata| Permissions {
+            mode: metadata.permissions().mode() & 0o7777,
+            owner: owner_names(metadata.uid(), metadata.gid()),
+        })
+    }
+
+    /// Always None, there are no unix permissions to capture
+    #[cfg(not(unix))]
+    pub(crate) fn of(_path: &Path) -> Option<Self> {
+        None
+    }
+}
+
+/// The user and group names for ids as `user:group`, falling back to the numeric id when a name can't be found
+#[cfg(unix)]
+pub(crate) fn owner_names(uid: u32, gid: u32) -> String {
+    let user = uzers::get_user_by_uid(uid).map_or_else(
+        || uid.to_string(),
+        |user| user.name().to_string_lossy().to_string(),
+    );
+    let group = uzers::get_group_by_gid(gid).map_or_else(
+        || gid.to_string(),
+        |group| group.name().to_string_lossy().to_string(),
+    );
+    format!("{user}:{group}")
+}
+
 impl Display for PathWithState {
+    #[allow(clippy::unnecessary_debug_formatting)]
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let state = &self.state;
         let path = &self.path;
@@ -30,6 +77,13 @@ impl Display for PathWithState {
     }
 }
 
+impl Display for Permissions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Permissions { mode, owner } = self;
+        write!(f, "(mode {mode:04o}, owned by {owner})")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -43,4 +97,21 @@ mod tests {
 
         assert_eq!(r#"[OK        ] "/lol""#, &format!("{p:width$}", width = 10));
     }
+
+    #[test]
+    fn permissions_display() {
+        let p = Permissions {
+            mode: 0o644,
+            owner: "root:staff".to_string(),
+        };
+
+        assert_eq!("(mode 0644, owned by root:staff)", &format!("{p}"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn owner_names_fall_back_to_ids() {
+        let unused = u32::MAX - 1;
+        assert_eq!(format!("{unused}:{unused}"), owner_names(unused, unused));
+    }
 }
@@ -0,0 +1,181 @@
+use crate::file_state::FileState;
+use crate::path_part::PartState;
+use std::fmt::Display;
+use std::path::PathBuf;
+
+/// A problem detected while diagnosing a program
+///
+/// Returned by `Program::problems()` ordered from most to least severe.
+/// Any problem, including warnings, fails `cargo whichp --strict`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Problem {
+    /// No valid executable matching the program name was found on the PATH
+    NotFound,
+
+    /// The program name is empty
+    BlankProgram,
+
+    /// The program name contains whitespace
+    ContainsWhitespace,
+
+    /// The program name contains control, zero-width, or other invisible characters
+    /// such as a byte order mark, often from copying a command from a web page
+    InvisibleCharacters(Vec<char>),
+
+    /// The program is a valid executable, but the dynamic loader can't find these
+    /// shared libraries it needs so it fails to start.
+    /// Only detected with `Which::check_shared_libraries`
+    MissingSharedLibrary(Vec<String>),
+
+    /// The program resolves from a directory that is not in `Which::allowed_dirs`
+    ResolvedFromUnapprovedDir(PathBuf),
+
+    /// The PATH does not contain any directories
+    EmptyPath,
+
+    /// The PATH environment variable is not set, common for daemons and services
+    UnsetPath,
+
+    /// The whole PATH is wrapped in quotes that are likely literal, so the first
+    /// and last directories include a quote character
+    QuotedPath,
+
+    /// A file matching the program name was found, but cannot be executed
+    InvalidFile(PathBuf, FileState),
+
+    /// A valid executable that never runs since one earlier on the PATH wins
+    Shadowed(PathBuf),
+
+    /// The executable that will run is much older than a valid copy later on the PATH,
+    /// it may be a stale build. Holds the winner then the newer copy.
+    /// Only detected with `Which::check_staleness`
+    OlderThanShadowed(PathBuf, PathBuf),
+
+    /// Both a `.cmd` or `.bat` script wrapper and a native `.exe` with the same
+    /// name are found, which one runs changes how arguments and exit codes are
+    /// handled. Holds the one that will run then the other.
+    /// Only detected with `Which::check_script_wrappers`
+    ScriptWrapperAndNative(PathBuf, PathBuf),
+
+    /// A PATH part that is missing, not a directory, or empty
+    InvalidPathPart(PathBuf, PartState),
+
+    /// A relative PATH part, which resolves differently depending on the current directory
+    RelativePathPart(PathBuf),
+
+    /// The same directory appears on the PATH more than once, with the original spellings
+    DuplicatePathPart(Vec<PathBuf>),
+}
+
+/// How bad a `Problem` is
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    /// The program cannot be executed
+    Error,
+
+    /// The program may still execute, but something needs attention
+    Warning,
+}
+
+impl Problem {
+    #[must_use]
+    pub fn severity(&self) -> Severity {
+        match self {
+            Problem::NotFound
+            | Problem::BlankProgram
+            | Problem::ContainsWhitespace
+            | Problem::InvisibleCharacters(_)
+            | Problem::EmptyPath
+            | Problem::UnsetPath
+            | Problem::MissingSharedLibrary(_)
+            | Problem::ResolvedFromUnapprovedDir(_) => Severity::Error,
+            Problem::InvalidFile(_, _)
+            | Problem::Shadowed(_)
+            | Problem::OlderThanShadowed(_, _)
+            | Problem::ScriptWrapperAndNative(_, _)
+            | Problem::QuotedPath
+            | Problem::InvalidPathPart(_, _)
+            | Problem::RelativePathPart(_)
+            | Problem::DuplicatePathPart(_) => Severity::Warning,
+        }
+    }
+}
+
+impl Display for Problem {
+    #[allow(clippy::unnecessary_debug_formatting)]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::NotFound => f.write_str("Program not found"),
+            Problem::BlankProgram => f.write_str("Program is blank"),
+            Problem::ContainsWhitespace => f.write_str("Program contains whitespace"),
+            Problem::InvisibleCharacters(chars) => {
+                let out = chars
+                    .iter()
+                    .map(|c| format!("U+{:04X}", u32::from(*c)))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, "Program contains invisible characters: {out}")
+            }
+            Problem::MissingSharedLibrary(names) => write!(
+                f,
+                "Program needs shared libraries that cannot be found: {}",
+                names.join(", ")
+            ),
+            Problem::ResolvedFromUnapprovedDir(dir) => write!(
+                f,
+                "Program resolves from {dir:?} which is not one of the allowed directories"
+            ),
+            Problem::EmptyPath => f.write_str("The PATH is empty"),
+            Problem::UnsetPath => f.write_str(
+                "PATH is not set in this process's environment, which is common for services launched outside a login shell",
+            ),
+            Problem::QuotedPath => f.write_str(
+                "The PATH is wrapped in quotes which are likely literal i.e. from `export PATH='\"/usr/bin:/bin\"'`, the first and last directories include a quote",
+            ),
+            Problem::InvalidFile(path, state) => {
+                write!(f, "[{state}] {path:?} - {}", state.details())
+            }
+            Problem::Shadowed(path) => {
+                write!(
+                    f,
+                    "{path:?} is shadowed by an executable earlier on the PATH"
+                )
+            }
+            Problem::OlderThanShadowed(winner, newer) => write!(
+                f,
+                "The executable that will run {winner:?} is older than another copy found later on the PATH {newer:?}, it may be stale"
+            ),
+            Problem::ScriptWrapperAndNative(winner, other) => {
+                let reason = if winner.parent() == other.parent() {
+                    "its extension comes first in PATHEXT"
+                } else {
+                    "its directory comes first on the PATH"
+                };
+                write!(
+                    f,
+                    "Both a script wrapper and a native executable are found, {winner:?} will run instead of {other:?} since {reason}"
+                )
+            }
+            Problem::InvalidPathPart(path, state) => {
+                write!(f, "[{state}] {path:?} - {}", state.details())
+            }
+            Problem::RelativePathPart(path) => write!(
+                f,
+                "PATH part {path:?} is relative and depends on the current working directory"
+            ),
+            Problem::DuplicatePathPart(paths) => {
+                let out = paths
+                    .iter()
+                    .map(|p| format!("{p:?}"))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(
+                    f,
+                    "The same directory appears on the PATH more than once: {out}"
+                )
+            }
+        }
+    }
+}
@@ -1,11 +1,19 @@
+use crate::edit::{self, MAX_EXPLAINED_EDITS};
+use crate::explicit::ExplicitPath;
 use crate::file_state::FileState;
-use crate::path_part::PathPart;
-use crate::path_with_state::PathWithState;
+use crate::listing::{self, DirCache};
+use crate::path_diff::PathDiff;
+use crate::path_part::{self, PartState, PathPart};
+use crate::path_with_state::{PathWithState, Permissions};
+use crate::problem::Problem;
+use crate::sudo::UnderSudo;
+use crate::suggest::{self, Subcommand, SuggestionDebug};
 use itertools::Itertools;
+use std::collections::HashMap;
 use std::ffi::OsString;
 use std::fmt::Display;
 use std::fmt::Write;
-use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
 
 /// Holds the results of a `Which::diagnose` call
 ///
@@ -14,35 +22,513 @@ use std::os::unix::ffi::OsStrExt;
 ///
 /// See the `Display` implementation.
 #[derive(Clone, Debug, Default)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Program {
     pub(crate) name: OsString,
     pub(crate) suggested: Option<Vec<OsString>>,
+    /// Full paths of `suggested`, in the same order
+    pub(crate) suggested_paths: Vec<PathBuf>,
+    pub(crate) suggest_truncated_at: Option<usize>,
+    pub(crate) guess_limit: usize,
+    pub(crate) suggest_total_file_limit: Option<usize>,
+    pub(crate) suggest_executables_only: bool,
+    pub(crate) suggestion_debug: Vec<SuggestionDebug>,
+    pub(crate) prefixed: Vec<PathBuf>,
+    pub(crate) separator_variants: Vec<PathBuf>,
+    pub(crate) local_bin: Option<PathBuf>,
+    pub(crate) subcommands: Vec<Subcommand>,
+    pub(crate) path_unset: bool,
     pub(crate) path_parts: Vec<PathPart>,
     pub(crate) found_files: Vec<PathWithState>,
+    /// Keyed by the path of each found file, only captured with `Which::show_permissions` on unix
+    pub(crate) permissions: HashMap<PathBuf, Permissions>,
+    pub(crate) unapproved_dir: Option<PathBuf>,
+    pub(crate) missing_shared_libraries: Vec<String>,
+    pub(crate) newer_shadowed: Option<PathBuf>,
+    /// The script wrapper or native executable that does not run, when both are found
+    pub(crate) script_wrapper_other: Option<PathBuf>,
+    pub(crate) explicit: Option<ExplicitPath>,
+    /// The expected name when the executable was only found by ignoring case, on Windows
+    pub(crate) case_folded: Option<OsString>,
+    pub(crate) quoted_path: bool,
+    pub(crate) unquoted_path: bool,
+    pub(crate) process_path_diff: Option<PathDiff>,
+    pub(crate) sudo: Option<UnderSudo>,
+}
+
+impl Program {
+    /// All problems detected, ordered from most to least severe
+    ///
+    /// An empty list means the program was found and nothing else needs attention.
+    #[must_use]
+    pub fn problems(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        let executable = self.executable().map(|found| &found.path);
+
+        if executable.is_none() {
+            problems.push(Problem::NotFound);
+            if self.name.is_empty() {
+                problems.push(Problem::BlankProgram);
+            }
+            if contains_whitespace(&self.name) {
+                problems.push(Problem::ContainsWhitespace);
+            }
+            let invisible = invisible_chars(&self.name);
+            if !invisible.is_empty() {
+                problems.push(Problem::InvisibleCharacters(invisible));
+            }
+        }
+        if !self.missing_shared_libraries.is_empty() {
+            problems.push(Problem::MissingSharedLibrary(
+                self.missing_shared_libraries.clone(),
+            ));
+        }
+        if let Some(dir) = &self.unapproved_dir {
+            problems.push(Problem::ResolvedFromUnapprovedDir(dir.clone()));
+        }
+        if self.quoted_path {
+            problems.push(Problem::QuotedPath);
+        }
+        if self.path_unset {
+            problems.push(Problem::UnsetPath);
+        } else if self.path_parts.is_empty() {
+            problems.push(Problem::EmptyPath);
+        }
+
+        for file in &self.found_files {
+            if file.state != FileState::Valid {
+                problems.push(Problem::InvalidFile(file.path.clone(), file.state.clone()));
+            } else if Some(&file.path) != executable {
+                problems.push(Problem::Shadowed(file.path.clone()));
+            }
+        }
+        if let (Some(winner), Some(newer)) = (executable, &self.newer_shadowed) {
+            problems.push(Problem::OlderThanShadowed(winner.clone(), newer.clone()));
+        }
+        if let (Some(winner), Some(other)) = (executable, &self.script_wrapper_other) {
+            problems.push(Problem::ScriptWrapperAndNative(
+                winner.clone(),
+                other.clone(),
+            ));
+        }
+
+        for part in &self.path_parts {
+            if part.state != PartState::Valid && !part.expected_empty {
+                problems.push(Problem::InvalidPathPart(
+                    part.original.clone(),
+                    part.state.clone(),
+                ));
+            }
+            if part.relative {
+                problems.push(Problem::RelativePathPart(part.original.clone()));
+            }
+        }
+
+        for parts in self.duplicate_path_parts() {
+            problems.push(Problem::DuplicatePathPart(
+                parts
+                    .into_iter()
+                    .map(|part| part.original.clone())
+                    .collect(),
+            ));
+        }
+
+        problems.sort_by_key(Problem::severity);
+        problems
+    }
+
+    /// Same as `problems()`, but consumes the program to move paths into the problems instead of cloning them
+    #[must_use]
+    pub fn into_problems(self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        let executable = self.executable().map(|found| found.path.clone());
+        let duplicates = self
+            .duplicate_path_parts()
+            .into_iter()
+            .map(|parts| {
+                parts
+                    .into_iter()
+                    .map(|part| part.original.clone())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let Program {
+            name,
+            path_unset,
+            path_parts,
+            found_files,
+            unapproved_dir,
+            missing_shared_libraries,
+            newer_shadowed,
+            script_wrapper_other,
+            quoted_path,
+            ..
+        } = self;
+
+        if executable.is_none() {
+            problems.push(Problem::NotFound);
+            if name.is_empty() {
+                problems.push(Problem::BlankProgram);
+            }
+            if contains_whitespace(&name) {
+                problems.push(Problem::ContainsWhitespace);
+            }
+            let invisible = invisible_chars(&name);
+            if !invisible.is_empty() {
+                problems.push(Problem::InvisibleCharacters(invisible));
+            }
+        }
+        if !missing_shared_libraries.is_empty() {
+            problems.push(Problem::MissingSharedLibrary(missing_shared_libraries));
+        }
+        if let Some(dir) = unapproved_dir {
+            problems.push(Problem::ResolvedFromUnapprovedDir(dir));
+        }
+        if quoted_path {
+            problems.push(Problem::QuotedPath);
+        }
+        if path_unset {
+            problems.push(Problem::UnsetPath);
+        } else if path_parts.is_empty() {
+            problems.push(Problem::EmptyPath);
+        }
+
+        for file in found_files {
+            if file.state != FileState::Valid {
+                problems.push(Problem::InvalidFile(file.path, file.state));
+            } else if Some(&file.path) != executable.as_ref() {
+                problems.push(Problem::Shadowed(file.path));
+            }
+        }
+        if let (Some(winner), Some(newer)) = (executable.clone(), newer_shadowed) {
+            problems.push(Problem::OlderThanShadowed(winner, newer));
+        }
+        if let (Some(winner), Some(other)) = (executable, script_wrapper_other) {
+            problems.push(Problem::ScriptWrapperAndNative(winner, other));
+        }
+
+        for part in path_parts {
+            let invalid = part.state != PartState::Valid && !part.expected_empty;
+            match (invalid, part.relative) {
+                (false, false) => {}
+                (false, true) => problems.push(Problem::RelativePathPart(part.original)),
+                (true, false) => problems.push(Problem::InvalidPathPart(part.original, part.state)),
+                (true, true) => {
+                    problems.push(Problem::InvalidPathPart(part.original.clone(), part.state));
+                    problems.push(Problem::RelativePathPart(part.original));
+                }
+            }
+        }
+
+        for parts in duplicates {
+            problems.push(Problem::DuplicatePathPart(parts));
+        }
+
+        problems.sort_by_key(Problem::severity);
+        problems
+    }
+
+    /// POSIX shell commands that fix detected problems, in the order to run them
+    ///
+    /// Only problems with a single safe fix are covered: a program that is not
+    /// found because the file lacks the executable bit, and a program given as
+    /// a path whose directory is not on the PATH. Empty when there's nothing to
+    /// fix or on Windows.
+    #[must_use]
+    pub fn remediation_commands(&self) -> Vec<String> {
+        let mut commands = Vec::new();
+        if !cfg!(unix) {
+            return commands;
+        }
+
+        let executable = self.executable();
+        if executable.is_none() {
+            if let Some(path) = self
+                .found_files
+                .iter()
+                .find(|file| file.state == FileState::NotExecutable)
+                .and_then(|file| shell_quote(&file.path))
+            {
+                commands.push(format!("chmod +x {path}"));
+            }
+        }
+
+        if let Some(explicit) = self.explicit.as_ref().filter(|e| !e.on_path) {
+            if let Some(dir) = executable.and_then(|_| shell_quote(&explicit.dir.absolute)) {
+                commands.push(format!(r#"export PATH="$PATH":{dir}"#));
+            }
+        }
+
+        commands
+    }
+
+    /// A plain English paragraph explaining why the executable that runs was chosen over the other files found
+    ///
+    /// The PATH is searched from the first directory to the last and the first
+    /// valid executable wins. Files before it are skipped since they can't run
+    /// and files after it are never reached.
+    #[must_use]
+    #[allow(clippy::unnecessary_debug_formatting)]
+    pub fn precedence_explanation(&self) -> String {
+        let name = &self.name;
+        let join = |paths: &[&PathBuf]| paths.iter().map(|p| format!("{p:?}")).join(", ");
+        let winner = match self.executable() {
+            Some(winner) => &winner.path,
+            None if self.found_files.is_empty() => {
+                return format!("Nothing named {name:?} was found, so nothing runs.");
+            }
+            None => {
+                let skipped = self.found_files.iter().map(|p| &p.path).collect::<Vec<_>>();
+                return format!(
+                    "Files named {name:?} were found but none of them are valid executables, so nothing runs. Skipped: {}.",
+                    join(&skipped)
+                );
+            }
+        };
+        if self.explicit.is_some() {
+            return format!(
+                "{winner:?} runs because it was given as a path, the PATH is not searched."
+            );
+        }
+
+        let position = self
+            .found_files
+            .iter()
+            .position(|p| &p.path == winner)
+            .unwrap_or_default();
+        let (before, after) = self.found_files.split_at(position);
+        let skipped = before.iter().map(|p| &p.path).collect::<Vec<_>>();
+        // A directory listed on the PATH twice finds the winner twice
+        let unreached = after
+            .iter()
+            .map(|p| &p.path)
+            .filter(|path| path != &winner)
+            .collect::<Vec<_>>();
+
+        let mut out = String::from(
+            "The PATH is searched from the first directory to the last and the first valid executable wins. ",
+        );
+        // Writing to a String cannot fail
+        if skipped.is_empty() && unreached.is_empty() {
+            let _ = write!(out, "{winner:?} runs because it's the only match.");
+            return out;
+        }
+        let _ = write!(
+            out,
+            "{winner:?} runs because it's the first valid match in PATH order"
+        );
+        if !skipped.is_empty() {
+            let _ = write!(
+                out,
+                "; {} {} earlier but can't run, so {} skipped",
+                join(&skipped),
+                if skipped.len() == 1 { "comes" } else { "come" },
+                if skipped.len() == 1 {
+                    "it's"
+                } else {
+                    "they're"
+                }
+            );
+        }
+        if !unreached.is_empty() {
+            let _ = write!(
+                out,
+                "; {} {} never reached",
+                join(&unreached),
+                if unreached.len() == 1 { "is" } else { "are" }
+            );
+        }
+        out.push('.');
+        out
+    }
+
+    /// Full paths of the executables suggested as alternate spellings of the program, closest first
+    ///
+    /// Empty when there are no suggestions or `Which::guess_limit` is zero.
+    #[must_use]
+    pub fn suggestions_with_paths(&self) -> Vec<PathBuf> {
+        self.suggested_paths.clone()
+    }
+
+    /// File names of suggestions beyond `Which::guess_limit`, closest first
+    ///
+    /// Returns up to `guess_limit + additional_limit` names starting with the
+    /// original suggestions. Reads the PATH directories found by the diagnosis
+    /// again, but doesn't repeat any other checks.
+    #[must_use]
+    pub fn more_suggestions(&self, additional_limit: usize) -> Vec<OsString> {
+        let (name, parts) = match &self.explicit {
+            Some(explicit) => (&explicit.name, std::slice::from_ref(&explicit.dir)),
+            None => (&self.name, self.path_parts.as_slice()),
+        };
+        let mut cache = DirCache::new();
+        listing::fill(&mut cache, parts.iter().map(|part| part.absolute.as_path()));
+        let (suggested, _) = suggest::spelling(
+            name,
+            &listing::from_cache(&cache, parts),
+            self.guess_limit + additional_limit,
+            self.suggest_total_file_limit,
+            self.suggest_executables_only,
+            false,
+        );
+
+        suggest::file_names(&suggested.unwrap_or_default())
+    }
+
+    /// Every suggestion candidate considered, closest first, with why it was or wasn't suggested
+    ///
+    /// Empty unless `Which::suggest_debug` is enabled.
+    #[must_use]
+    pub fn suggestion_debug(&self) -> &[SuggestionDebug] {
+        &self.suggestion_debug
+    }
+
+    /// The closest suggestion and the edits from the program name to it, when only a few edits apart
+    pub(crate) fn closest_edits(&self) -> Option<(String, Vec<edit::Edit>)> {
+        let closest = self
+            .suggested
+            .as_ref()?
+            .first()?
+            .to_string_lossy()
+            .to_string();
+        let edits = edit::edits(&self.name.to_string_lossy(), &closest, MAX_EXPLAINED_EDITS)?;
+        Some((closest, edits))
+    }
+
+    /// The first valid executable on the PATH, the one that will run
+    pub(crate) fn executable(&self) -> Option<&PathWithState> {
+        self.found_files
+            .iter()
+            .find(|p| matches!(p.state, FileState::Valid))
+    }
+
+    /// Groups of PATH parts that point at the same directory, in PATH order
+    pub(crate) fn duplicate_path_parts(&self) -> Vec<Vec<&PathPart>> {
+        path_part::duplicates(&self.path_parts)
+    }
+}
+
+/// Single quote a path for a POSIX shell, None when it's not valid UTF-8
+fn shell_quote(path: &std::path::Path) -> Option<String> {
+    path.to_str()
+        .map(|path| format!("'{}'", path.replace('\'', r"'\''")))
+}
+
+impl From<Program> for Vec<Problem> {
+    fn from(program: Program) -> Self {
+        program.into_problems()
+    }
 }
 
 pub(crate) fn contains_whitespace(name: &OsString) -> bool {
-    (name).as_bytes().iter().any(u8::is_ascii_whitespace)
+    name.to_string_lossy()
+        .bytes()
+        .any(|byte| byte.is_ascii_whitespace())
 }
 
+/// Control, zero-width, and other characters that don't show up when printed, in order without repeats
+///
+/// Copying a command from a web page or document can include them, i.e. a zero-width
+/// space (U+200B) or a byte order mark (U+FEFF).
+pub(crate) fn invisible_chars(name: &OsString) -> Vec<char> {
+    name.to_string_lossy()
+        .chars()
+        .filter(|c| {
+            c.is_control()
+                || matches!(
+                    c,
+                    '\u{00AD}'
+                        | '\u{034F}'
+                        | '\u{061C}'
+                        | '\u{115F}'
+                        | '\u{1160}'
+                        | '\u{17B4}'
+                        | '\u{17B5}'
+                        | '\u{180E}'
+                        | '\u{200B}'..='\u{200F}'
+                        | '\u{202A}'..='\u{202E}'
+                        | '\u{2060}'..='\u{206F}'
+                        | '\u{3164}'
+                        | '\u{FE00}'..='\u{FE0F}'
+                        | '\u{FEFF}'
+                        | '\u{FFA0}'
+                        | '\u{FFF9}'..='\u{FFFB}'
+                )
+        })
+        .unique()
+        .collect()
+}
+
+/// Commands starting with the program name beyond this are summarized as a count
+const MAX_PREFIXED_SHOWN: usize = 10;
+
 impl Display for Program {
-    #[allow(clippy::too_many_lines)]
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_report(f, true, true)
+    }
+}
+
+impl Program {
+    /// Render the same output as `Display` without padding states to a common width
+    ///
+    /// The `Display` output aligns states into columns for a terminal. This
+    /// produces tight lines, with no trailing whitespace, for embedding the
+    /// output in other contexts such as log messages or JSON strings.
+    #[must_use]
+    pub fn render_plain_ascii(&self) -> String {
+        let mut out = String::new();
+        // Writing to a String cannot fail
+        let _ = self.write_report(&mut out, false, true);
+        out
+    }
+
+    /// Writes the report, when `padded` is false states are not padded to a common width
+    ///
+    /// When `remediation` is false the commands from `remediation_commands()` are left out.
+    #[allow(clippy::too_many_lines)]
+    #[allow(clippy::unnecessary_debug_formatting)]
+    pub(crate) fn write_report(
+        &self,
+        f: &mut dyn Write,
+        padded: bool,
+        remediation: bool,
+    ) -> std::fmt::Result {
         let Program {
             name,
             suggested,
+            suggested_paths: _,
+            suggest_truncated_at,
+            guess_limit: _,
+            suggest_total_file_limit: _,
+            suggest_executables_only: _,
+            suggestion_debug,
+            prefixed,
+            separator_variants,
+            local_bin,
+            subcommands,
+            path_unset,
             path_parts,
             found_files,
+            permissions,
+            unapproved_dir,
+            missing_shared_libraries,
+            newer_shadowed,
+            script_wrapper_other,
+            explicit,
+            case_folded,
+            quoted_path,
+            unquoted_path,
+            process_path_diff,
+            sudo,
         } = &self;
 
-        let executable = found_files
-            .iter()
-            .find(|p| matches!(p.state, FileState::Valid));
+        let executable = self.executable();
 
         let file_state_width = found_files
             .iter()
             .map(|p| format!("{}", p.state).len())
             .max()
+            .filter(|_| padded)
             .unwrap_or_default();
 
         let part_width = self
@@ -50,14 +536,39 @@ impl Display for Program {
             .iter()
             .map(|part| format!("{}", part.state).len())
             .max()
+            .filter(|_| padded)
             .unwrap_or(0);
 
         // Found/Not-found
         if let Some(found) = executable {
             let file = &found.path;
-            writeln!(f, r#"Program {name:?} found at {file:?}"#)?;
+            writeln!(f, "Program {name:?} found at {file:?}")?;
+
+            if !missing_shared_libraries.is_empty() {
+                writeln!(
+                    f,
+                    "Error: {}",
+                    Problem::MissingSharedLibrary(missing_shared_libraries.clone())
+                )?;
+                f.write_str("Help: Install the packages that provide them, or add the directory they're in to LD_LIBRARY_PATH\n")?;
+            }
+
+            if let Some(expected) = case_folded {
+                writeln!(
+                    f,
+                    "Info: The file name only matches {expected:?} when ignoring case, which works on Windows but not on case-sensitive systems"
+                )?;
+            }
+
+            if let Some(dir) = unapproved_dir {
+                writeln!(
+                    f,
+                    "Warning: {}",
+                    Problem::ResolvedFromUnapprovedDir(dir.clone())
+                )?;
+            }
         } else {
-            writeln!(f, r#"Program {name:?} not found"#)?;
+            writeln!(f, "Program {name:?} not found")?;
 
             if self.name.is_empty() {
                 writeln!(f, "Warning: Program is blank")?;
@@ -65,6 +576,38 @@ impl Display for Program {
             if contains_whitespace(&self.name) {
                 writeln!(f, "Warning: Program contains whitespace")?;
             }
+            let invisible = invisible_chars(&self.name);
+            if !invisible.is_empty() {
+                writeln!(f, "Warning: {}", Problem::InvisibleCharacters(invisible))?;
+            }
+        }
+        if let Some(ExplicitPath {
+            dir,
+            name: file_name,
+            on_path,
+        }) = explicit
+        {
+            let dir_path = &dir.absolute;
+            let bare = file_name.to_string_lossy();
+            if *on_path {
+                writeln!(
+                    f,
+                    "Info: The directory {dir_path:?} is on the PATH, bare `{bare}` searches it too"
+                )?;
+            } else if executable.is_some() {
+                writeln!(
+                    f,
+                    "Info: The directory {dir_path:?} is not on the PATH, so bare `{bare}` won't resolve, though the path {name:?} works"
+                )?;
+            } else {
+                writeln!(
+                    f,
+                    "Info: The directory {dir_path:?} is not on the PATH, so bare `{bare}` won't resolve either"
+                )?;
+            }
+            if dir.state != PartState::Valid && !dir.expected_empty {
+                writeln!(f, "Warning: Directory {dir} - {}", dir.state.details())?;
+            }
         }
         f.write_char('\n')?;
 
@@ -83,13 +626,35 @@ impl Display for Program {
                     write!(f, "- ")?;
                 }
 
-                writeln!(f, "{path:file_state_width$}")?;
+                write!(f, "{path:file_state_width$}")?;
+                if let Some(permissions) = permissions.get(&path.path) {
+                    write!(f, " {permissions}")?;
+                }
+                f.write_char('\n')?;
             }
             writeln!(
                 f,
                 "Help: Ensure the one you want comes first and is [{valid:file_state_width$}]",
                 valid = FileState::Valid
             )?;
+            if let (Some(winner), Some(newer)) = (executable, newer_shadowed) {
+                writeln!(
+                    f,
+                    "Warning: {}",
+                    Problem::OlderThanShadowed(winner.path.clone(), newer.clone())
+                )?;
+            }
+            if let (Some(winner), Some(other)) = (executable, script_wrapper_other) {
+                writeln!(
+                    f,
+                    "Warning: {}",
+                    Problem::ScriptWrapperAndNative(winner.path.clone(), other.clone())
+                )?;
+                writeln!(
+                    f,
+                    "Help: A `.cmd` or `.bat` wrapper runs through `cmd.exe`, which parses special characters in arguments (i.e. `%`, `^`, and `&`) again and may not exit with the status of the program it wraps"
+                )?;
+            }
             f.write_str("Explanation:\n")?;
             for state in found_files.iter().map(|p| p.state.clone()).unique() {
                 let details = state.details();
@@ -119,11 +684,103 @@ impl Display for Program {
                 .join(", ");
 
             writeln!(f, "{out}")?;
+            if let Some((closest, edits)) = self.closest_edits() {
+                let edits = edits
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<String>>()
+                    .join(", then ");
+                writeln!(
+                    f,
+                    "Help: You wrote `{}`, the closest match is `{closest}`: {edits}",
+                    name.to_string_lossy()
+                )?;
+            }
+            if let Some(limit) = suggest_truncated_at {
+                writeln!(f, "Info: Suggestion scan truncated after {limit} files")?;
+            }
+            f.write_char('\n')?;
+        }
+
+        if !suggestion_debug.is_empty() {
+            writeln!(
+                f,
+                "Info: Suggestion candidates for {name:?} (score, name, outcome):"
+            )?;
+            for candidate in suggestion_debug {
+                writeln!(f, "  {candidate}")?;
+            }
+            f.write_char('\n')?;
+        }
+
+        // Local bin
+        if let Some(path) = local_bin {
+            let dir = path.parent().unwrap_or(path);
+            writeln!(
+                f,
+                "Info: Found {path:?} in a project local directory that is not on the PATH"
+            )?;
+            if dir.ends_with(Path::new("node_modules").join(".bin")) {
+                writeln!(
+                    f,
+                    "Help: Run it via `npx {}` or add {dir:?} to the PATH",
+                    name.to_string_lossy()
+                )?;
+            } else {
+                writeln!(f, "Help: Add {dir:?} to the PATH")?;
+            }
+            f.write_char('\n')?;
+        }
+
+        // Separator variants
+        if !separator_variants.is_empty() {
+            for path in separator_variants {
+                writeln!(
+                    f,
+                    "Info: Found {:?} at {path:?}, you may have typed the separator differently",
+                    path.file_name().unwrap_or(path.as_os_str())
+                )?;
+            }
+            f.write_char('\n')?;
+        }
+
+        // Prefixed
+        if !prefixed.is_empty() {
+            let out = prefixed
+                .iter()
+                .take(MAX_PREFIXED_SHOWN)
+                .map(|p| format!("{:?}", p.file_name().unwrap_or(p.as_os_str())))
+                .collect::<Vec<String>>()
+                .join(", ");
+            writeln!(
+                f,
+                "Info: Commands starting with {name:?}, the name may be missing a suffix:"
+            )?;
+            writeln!(f, "      {out}")?;
+            if prefixed.len() > MAX_PREFIXED_SHOWN {
+                writeln!(f, "      and {} more", prefixed.len() - MAX_PREFIXED_SHOWN)?;
+            }
+            f.write_char('\n')?;
+        }
+
+        // Subcommands
+        if !subcommands.is_empty() {
+            for subcommand in subcommands {
+                writeln!(f, "Info: {subcommand}")?;
+            }
             f.write_char('\n')?;
         }
 
         // PATH parts
-        if path_parts.is_empty() {
+        if *quoted_path {
+            writeln!(f, "Warning: {}", Problem::QuotedPath)?;
+            if *unquoted_path {
+                f.write_str("Info: Diagnosing the PATH with the quotes removed\n")?;
+            }
+        }
+        if *path_unset {
+            writeln!(f, "Warning: {}", Problem::UnsetPath)?;
+        } else if path_parts.is_empty() {
             f.write_str("Warning: The PATH is empty\n")?;
         } else {
             f.write_str(
@@ -143,6 +800,9 @@ impl Display for Program {
                 }
 
                 writeln!(f, "{part:part_width$}")?;
+                if let Some(hint) = &part.hint {
+                    writeln!(f, "    Help: {hint}")?;
+                }
             }
             f.write_str("Explanation:\n")?;
             for state in path_parts.iter().map(|p| p.state.clone()).unique() {
@@ -151,6 +811,74 @@ impl Display for Program {
             }
         }
 
+        // Remediation
+        let commands = self.remediation_commands();
+        if remediation && !commands.is_empty() {
+            f.write_char('\n')?;
+            f.write_str("To fix, run:\n")?;
+            for command in commands {
+                writeln!(f, "  {command}")?;
+            }
+        }
+
+        // Compared to the process PATH
+        if let Some(diff) = process_path_diff {
+            f.write_char('\n')?;
+            if diff.is_empty() {
+                f.write_str(
+                    "Info: The PATH has the same directories as the current process PATH\n",
+                )?;
+            }
+            if !diff.added.is_empty() {
+                f.write_str(
+                    "Info: These directories were added relative to the current process PATH:\n",
+                )?;
+                for dir in &diff.added {
+                    writeln!(f, "  + {dir:?}")?;
+                }
+            }
+            if !diff.removed.is_empty() {
+                f.write_str(
+                    "Info: These directories were removed relative to the current process PATH:\n",
+                )?;
+                for dir in &diff.removed {
+                    writeln!(f, "  - {dir:?}")?;
+                }
+            }
+        }
+
+        // Sudo
+        match sudo {
+            Some(UnderSudo::Diagnosed {
+                secure_path,
+                program,
+            }) => {
+                f.write_char('\n')?;
+                writeln!(
+                    f,
+                    "Info: Under sudo the PATH is replaced by `secure_path` {secure_path:?}"
+                )?;
+                writeln!(f, "Info: Diagnosis under sudo:")?;
+                let report = if padded {
+                    program.to_string()
+                } else {
+                    program.render_plain_ascii()
+                };
+                for line in report.lines() {
+                    if line.is_empty() {
+                        f.write_char('\n')?;
+                    } else {
+                        writeln!(f, "  {line}")?;
+                    }
+                }
+            }
+            Some(UnderSudo::Unknown(note)) => {
+                f.write_char('\n')?;
+                writeln!(f, "Info: {note}")?;
+            }
+            None => {}
+        }
+
         Ok(())
     }
 }
@@ -158,6 +886,7 @@ impl Display for Program {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::listing::DirCache;
 
     #[test]
     fn empty_program_name() {
@@ -168,4 +897,60 @@ mod tests {
     fn check_whitespace_program() {
         assert!(contains_whitespace(&OsString::from("lol ")));
     }
+
+    #[test]
+    fn check_shell_quote() {
+        assert_eq!(
+            Some(r"'/it'\''s/bin'".to_string()),
+            shell_quote(std::path::Path::new("/it's/bin"))
+        );
+    }
+
+    #[test]
+    fn check_invisible_chars() {
+        assert_eq!(
+            vec!['\u{200B}'],
+            invisible_chars(&OsString::from("bun\u{200B}dle\u{200B}"))
+        );
+        assert_eq!(
+            vec!['\u{FEFF}', '\t'],
+            invisible_chars(&OsString::from("\u{FEFF}bundle\t"))
+        );
+        assert!(invisible_chars(&OsString::from("bündle")).is_empty());
+    }
+
+    #[test]
+    fn plain_ascii_has_no_padding() {
+        let cwd = std::env::current_dir().unwrap();
+        let program = Program {
+            name: OsString::from("lol"),
+            suggested: Some(vec![OsString::from("rofl")]),
+            path_parts: vec![
+                PathPart::new(&cwd, &cwd, &DirCache::new()),
+                PathPart::new(&cwd, &cwd.join("does-not-exist"), &DirCache::new()),
+            ],
+            found_files: vec![
+                PathWithState {
+                    path: cwd.join("lol"),
+                    state: FileState::Valid,
+                },
+                PathWithState {
+                    path: cwd.join("does-not-exist").join("lol"),
+                    state: FileState::NotExecutable,
+                },
+            ],
+            ..Program::default()
+        };
+
+        let padded = program.to_string();
+        assert!(padded.contains("[OK     ]"));
+
+        let plain = program.render_plain_ascii();
+        assert!(plain.contains("[OK]"));
+        assert!(plain.contains("[MISSING]"));
+        assert!(plain.contains("[NOT EXE]"));
+        for line in plain.lines() {
+            assert_eq!(line.trim_end(), line);
+        }
+    }
 }
@@ -0,0 +1,380 @@
+use crate::explicit::ExplicitPath;
+use crate::file_state::FileState;
+use crate::path_diff::PathDiff;
+use crate::path_part::{PartHint, PartState, PathPart};
+use crate::path_with_state::{PathWithState, Permissions};
+use crate::program::Program;
+use crate::sudo::UnderSudo;
+use crate::suggest::{Subcommand, SuggestionDebug, SuggestionDisposition};
+use serde::{Deserialize, Serialize};
+use std::ffi::{OsStr, OsString};
+use std::path::PathBuf;
+
+/// A `Program` in a form that can be serialized, to save a diagnosis and view it later
+///
+/// Names and paths are stored as UTF-8 strings, bytes that are not valid UTF-8
+/// are replaced with `U+FFFD`. Converting back into a `Program` does not access
+/// the filesystem.
+///
+/// Example:
+///
+/// ```rust,no_run
+/// use which_problem::{Program, SavedProgram, Which};
+///
+/// let program = Which::new("bundle").diagnose().unwrap();
+/// let saved = SavedProgram::from(&program);
+/// // Serialize `saved` with any serde format, then later
+/// let program = Program::from(saved);
+/// println!("{program}");
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct SavedProgram {
+    name: String,
+    suggested: Option<Vec<String>>,
+    suggested_paths: Vec<String>,
+    suggest_truncated_at: Option<usize>,
+    guess_limit: usize,
+    suggest_total_file_limit: Option<usize>,
+    suggest_executables_only: bool,
+    suggestion_debug: Vec<SavedSuggestionDebug>,
+    prefixed: Vec<String>,
+    separator_variants: Vec<String>,
+    local_bin: Option<String>,
+    subcommands: Vec<SavedSubcommand>,
+    path_unset: bool,
+    path_parts: Vec<SavedPathPart>,
+    /// Found files, with their permissions when they were captured
+    found_files: Vec<SavedFile>,
+    unapproved_dir: Option<String>,
+    missing_shared_libraries: Vec<String>,
+    newer_shadowed: Option<String>,
+    script_wrapper_other: Option<String>,
+    explicit: Option<SavedExplicitPath>,
+    case_folded: Option<String>,
+    quoted_path: bool,
+    unquoted_path: bool,
+    process_path_diff: Option<SavedPathDiff>,
+    sudo: Option<SavedUnderSudo>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct SavedSuggestionDebug {
+    name: String,
+    score: f64,
+    disposition: SuggestionDisposition,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct SavedSubcommand {
+    command: String,
+    program: String,
+    path: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct SavedPathPart {
+    absolute: String,
+    cwd: String,
+    state: PartState,
+    original: String,
+    normalized: String,
+    hint: Option<SavedPartHint>,
+    relative: bool,
+    expected_empty: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum SavedPartHint {
+    ParentDir(String),
+    CaseMismatch(String),
+    Inaccessible(String),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct SavedFile {
+    path: String,
+    state: FileState,
+    permissions: Option<SavedPermissions>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct SavedPermissions {
+    mode: u32,
+    owner: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct SavedExplicitPath {
+    dir: SavedPathPart,
+    name: String,
+    on_path: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct SavedPathDiff {
+    added: Vec<String>,
+    removed: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum SavedUnderSudo {
+    Diagnosed {
+        secure_path: String,
+        program: Box<SavedProgram>,
+    },
+    Unknown(String),
+}
+
+impl From<&Program> for SavedProgram {
+    fn from(program: &Program) -> Self {
+        let found_files = program
+            .found_files
+            .iter()
+            .map(|file| SavedFile {
+                path: lossy(&file.path),
+                state: file.state.clone(),
+                permissions: program.permissions.get(&file.path).map(|permissions| {
+                    SavedPermissions {
+                        mode: permissions.mode,
+                        owner: permissions.owner.clone(),
+                    }
+                }),
+            })
+            .collect();
+
+        SavedProgram {
+            name: lossy(&program.name),
+            suggested: program.suggested.as_deref().map(lossy_all),
+            suggested_paths: lossy_all(&program.suggested_paths),
+            suggest_truncated_at: program.suggest_truncated_at,
+            guess_limit: program.guess_limit,
+            suggest_total_file_limit: program.suggest_total_file_limit,
+            suggest_executables_only: program.suggest_executables_only,
+            suggestion_debug: program
+                .suggestion_debug
+                .iter()
+                .map(|debug| SavedSuggestionDebug {
+                    name: lossy(&debug.name),
+                    score: debug.score,
+                    disposition: debug.disposition,
+                })
+                .collect(),
+            prefixed: lossy_all(&program.prefixed),
+            separator_variants: lossy_all(&program.separator_variants),
+            local_bin: program.local_bin.as_deref().map(lossy),
+            subcommands: program
+                .subcommands
+                .iter()
+                .map(|subcommand| SavedSubcommand {
+                    command: lossy(&subcommand.command),
+                    program: lossy(&subcommand.program),
+                    path: lossy(&subcommand.path),
+                })
+                .collect(),
+            path_unset: program.path_unset,
+            path_parts: program.path_parts.iter().map(SavedPathPart::from).collect(),
+            found_files,
+            unapproved_dir: program.unapproved_dir.as_deref().map(lossy),
+            missing_shared_libraries: program.missing_shared_libraries.clone(),
+            newer_shadowed: program.newer_shadowed.as_deref().map(lossy),
+            script_wrapper_other: program.script_wrapper_other.as_deref().map(lossy),
+            explicit: program.explicit.as_ref().map(|explicit| SavedExplicitPath {
+                dir: SavedPathPart::from(&explicit.dir),
+                name: lossy(&explicit.name),
+                on_path: explicit.on_path,
+            }),
+            case_folded: program.case_folded.as_deref().map(lossy),
+            quoted_path: program.quoted_path,
+            unquoted_path: program.unquoted_path,
+            process_path_diff: program
+                .process_path_diff
+                .as_ref()
+                .map(|diff| SavedPathDiff {
+                    added: lossy_all(&diff.added),
+                    removed: lossy_all(&diff.removed),
+                }),
+            sudo: program.sudo.as_ref().map(|sudo| match sudo {
+                UnderSudo::Diagnosed {
+                    secure_path,
+                    program,
+                } => SavedUnderSudo::Diagnosed {
+                    secure_path: lossy(secure_path),
+                    program: Box::new(SavedProgram::from(program.as_ref())),
+                },
+                UnderSudo::Unknown(reason) => SavedUnderSudo::Unknown(reason.clone()),
+            }),
+        }
+    }
+}
+
+impl From<SavedProgram> for Program {
+    fn from(saved: SavedProgram) -> Self {
+        let permissions = saved
+            .found_files
+            .iter()
+            .filter_map(|file| {
+                let permissions = file.permissions.as_ref()?;
+                Some((
+                    PathBuf::from(&file.path),
+                    Permissions {
+                        mode: permissions.mode,
+                        owner: permissions.owner.clone(),
+                    },
+                ))
+            })
+            .collect();
+
+        Program {
+            name: OsString::from(saved.name),
+            suggested: saved
+                .suggested
+                .map(|names| names.into_iter().map(OsString::from).collect()),
+            suggested_paths: paths(saved.suggested_paths),
+            suggest_truncated_at: saved.suggest_truncated_at,
+            guess_limit: saved.guess_limit,
+            suggest_total_file_limit: saved.suggest_total_file_limit,
+            suggest_executables_only: saved.suggest_executables_only,
+            suggestion_debug: saved
+                .suggestion_debug
+                .into_iter()
+                .map(|debug| SuggestionDebug {
+                    name: OsString::from(debug.name),
+                    score: debug.score,
+                    disposition: debug.disposition,
+                })
+                .collect(),
+            prefixed: paths(saved.prefixed),
+            separator_variants: paths(saved.separator_variants),
+            local_bin: saved.local_bin.map(PathBuf::from),
+            subcommands: saved
+                .subcommands
+                .into_iter()
+                .map(|subcommand| Subcommand {
+                    command: OsString::from(subcommand.command),
+                    program: OsString::from(subcommand.program),
+                    path: PathBuf::from(subcommand.path),
+                })
+                .collect(),
+            path_unset: saved.path_unset,
+            path_parts: saved.path_parts.into_iter().map(PathPart::from).collect(),
+            found_files: saved
+                .found_files
+                .into_iter()
+                .map(|file| PathWithState {
+                    path: PathBuf::from(file.path),
+                    state: file.state,
+                })
+                .collect(),
+            permissions,
+            unapproved_dir: saved.unapproved_dir.map(PathBuf::from),
+            missing_shared_libraries: saved.missing_shared_libraries,
+            newer_shadowed: saved.newer_shadowed.map(PathBuf::from),
+            script_wrapper_other: saved.script_wrapper_other.map(PathBuf::from),
+            explicit: saved.explicit.map(|explicit| ExplicitPath {
+                dir: PathPart::from(explicit.dir),
+                name: OsString::from(explicit.name),
+                on_path: explicit.on_path,
+            }),
+            case_folded: saved.case_folded.map(OsString::from),
+            quoted_path: saved.quoted_path,
+            unquoted_path: saved.unquoted_path,
+            process_path_diff: saved.process_path_diff.map(|diff| PathDiff {
+                added: paths(diff.added),
+                removed: paths(diff.removed),
+            }),
+            sudo: saved.sudo.map(|sudo| match sudo {
+                SavedUnderSudo::Diagnosed {
+                    secure_path,
+                    program,
+                } => UnderSudo::Diagnosed {
+                    secure_path: OsString::from(secure_path),
+                    program: Box::new(Program::from(*program)),
+                },
+                SavedUnderSudo::Unknown(reason) => UnderSudo::Unknown(reason),
+            }),
+        }
+    }
+}
+
+impl From<&PathPart> for SavedPathPart {
+    fn from(part: &PathPart) -> Self {
+        SavedPathPart {
+            absolute: lossy(&part.absolute),
+            cwd: lossy(&part.cwd),
+            state: part.state.clone(),
+            original: lossy(&part.original),
+            normalized: lossy(&part.normalized),
+            hint: part.hint.as_ref().map(|hint| match hint {
+                PartHint::ParentDir(path) => SavedPartHint::ParentDir(lossy(path)),
+                PartHint::CaseMismatch(path) => SavedPartHint::CaseMismatch(lossy(path)),
+                PartHint::Inaccessible(path) => SavedPartHint::Inaccessible(lossy(path)),
+            }),
+            relative: part.relative,
+            expected_empty: part.expected_empty,
+        }
+    }
+}
+
+impl From<SavedPathPart> for PathPart {
+    fn from(part: SavedPathPart) -> Self {
+        PathPart {
+            absolute: PathBuf::from(part.absolute),
+            cwd: PathBuf::from(part.cwd),
+            state: part.state,
+            original: PathBuf::from(part.original),
+            normalized: PathBuf::from(part.normalized),
+            hint: part.hint.map(|hint| match hint {
+                SavedPartHint::ParentDir(path) => PartHint::ParentDir(PathBuf::from(path)),
+                SavedPartHint::CaseMismatch(path) => PartHint::CaseMismatch(PathBuf::from(path)),
+                SavedPartHint::Inaccessible(path) => PartHint::Inaccessible(PathBuf::from(path)),
+            }),
+            relative: part.relative,
+            expected_empty: part.expected_empty,
+        }
+    }
+}
+
+fn lossy<S: AsRef<OsStr> + ?Sized>(value: &S) -> String {
+    value.as_ref().to_string_lossy().into_owned()
+}
+
+fn lossy_all<S: AsRef<OsStr>>(values: &[S]) -> Vec<String> {
+    values.iter().map(lossy).collect()
+}
+
+fn paths(values: Vec<String>) -> Vec<PathBuf> {
+    values.into_iter().map(PathBuf::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn non_utf8_names_are_lossy() {
+        use std::os::unix::ffi::OsStrExt;
+        use std::path::Path;
+
+        let name = OsStr::from_bytes(b"lo\xffl");
+        let program = Program {
+            name: name.to_os_string(),
+            found_files: vec![PathWithState {
+                path: Path::new("/bin").join(name),
+                state: FileState::Valid,
+            }],
+            ..Program::default()
+        };
+
+        let loaded = Program::from(SavedProgram::from(&program));
+        assert_eq!(OsString::from("lo\u{FFFD}l"), loaded.name);
+        assert_eq!(
+            vec![PathWithState {
+                path: PathBuf::from("/bin/lo\u{FFFD}l"),
+                state: FileState::Valid,
+            }],
+            loaded.found_files
+        );
+    }
+}
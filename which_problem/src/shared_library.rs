@@ -0,0 +1,65 @@
+use std::path::Path;
+use std::process::Command;
+
+/// Absolute so an `ldd` earlier on the PATH of the process is never run
+pub(crate) const LDD: &str = "/usr/bin/ldd";
+
+/// Names of the shared libraries `binary` needs that the dynamic loader can't find, according to `ldd`
+///
+/// Empty when `ldd` can't be run or the file isn't a dynamic executable, i.e. a script.
+pub(crate) fn missing(ldd: &Path, binary: &Path) -> Vec<String> {
+    match Command::new(ldd).arg(binary).output() {
+        Ok(output) if output.status.success() => {
+            parse_ldd(&String::from_utf8_lossy(&output.stdout))
+        }
+        Ok(_) | Err(_) => Vec::new(),
+    }
+}
+
+/// Libraries listed as `libfoo.so.1 => not found` in the output of `ldd`
+fn parse_ldd(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().strip_suffix("=> not found"))
+        .map(|name| name.trim().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ldd_not_found() {
+        let output = "\tlinux-vdso.so.1 (0x00007ffd5a5f6000)
+\tlibbogus.so.1 => not found
+\tlibc.so.6 => /lib/x86_64-linux-gnu/libc.so.6 (0x00007f2c1b000000)
+\tlibmissing.so => not found
+";
+        assert_eq!(
+            vec!["libbogus.so.1".to_string(), "libmissing.so".to_string()],
+            parse_ldd(output)
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn missing_with_mocked_ldd() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let ldd = tmp_dir.path().join("ldd");
+        std::fs::write(
+            &ldd,
+            "#!/bin/sh\nprintf '\\tlibbogus.so.1 => not found\\n\\tlibc.so.6 => /lib/libc.so.6\\n'\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&ldd, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert_eq!(
+            vec!["libbogus.so.1".to_string()],
+            missing(&ldd, Path::new("/bin/sh"))
+        );
+        assert!(missing(&tmp_dir.path().join("no_ldd"), Path::new("/bin/sh")).is_empty());
+    }
+}
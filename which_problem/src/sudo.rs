@@ -0,0 +1,89 @@
+use crate::program::Program;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+/// Where to find the PATH that `sudo` uses
+///
+/// When `secure_path` is set in sudoers, `sudo` replaces the user's PATH
+/// with it. A program that works without `sudo` might not be found with it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SecurePath {
+    /// Read `secure_path` from a sudoers file i.e. `/etc/sudoers`
+    Sudoers(PathBuf),
+
+    /// Use this value as `secure_path` i.e. `OsString::from("/usr/sbin:/usr/bin")`
+    Explicit(OsString),
+}
+
+impl Default for SecurePath {
+    fn default() -> Self {
+        SecurePath::Sudoers(PathBuf::from("/etc/sudoers"))
+    }
+}
+
+/// The result of diagnosing a program with the PATH used by `sudo`
+#[derive(Clone, Debug)]
+pub(crate) enum UnderSudo {
+    /// The program was diagnosed against `secure_path`
+    Diagnosed {
+        secure_path: OsString,
+        program: Box<Program>,
+    },
+
+    /// The PATH used by sudo could not be determined
+    Unknown(String),
+}
+
+/// Find the `secure_path` set by `Defaults` lines in sudoers, the last one wins
+///
+/// User, host, and command specific defaults such as `Defaults:root` are ignored.
+pub(crate) fn parse_secure_path(sudoers: &str) -> Option<OsString> {
+    sudoers
+        .lines()
+        .map(str::trim)
+        .filter_map(|line| line.strip_prefix("Defaults"))
+        .filter(|rest| rest.starts_with(char::is_whitespace))
+        .flat_map(|rest| rest.split(','))
+        .filter_map(|option| {
+            let (key, value) = option.split_once('=')?;
+            (key.trim() == "secure_path").then(|| OsString::from(value.trim().trim_matches('"')))
+        })
+        .next_back()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_quoted_secure_path() {
+        let sudoers = r#"
+# This is a comment
+Defaults	env_reset
+Defaults	secure_path="/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin"
+root	ALL=(ALL:ALL) ALL
+"#;
+        assert_eq!(
+            Some(OsString::from(
+                "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin"
+            )),
+            parse_secure_path(sudoers)
+        );
+    }
+
+    #[test]
+    fn parses_unquoted_secure_path_in_option_list() {
+        let sudoers = "Defaults env_reset, secure_path = /usr/bin:/bin\n";
+        assert_eq!(
+            Some(OsString::from("/usr/bin:/bin")),
+            parse_secure_path(sudoers)
+        );
+    }
+
+    #[test]
+    fn ignores_user_specific_defaults() {
+        let sudoers = "Defaults:schneems secure_path=/nope\n";
+        assert_eq!(None, parse_secure_path(sudoers));
+    }
+}
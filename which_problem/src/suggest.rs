@@ -1,68 +1,290 @@
-use crate::path_part::PathPart;
+use crate::file_state::{file_state, FileState};
+use crate::listing::Listing;
 use itertools::Itertools;
 use rayon::prelude::*;
 use std::collections::HashSet;
 use std::ffi::OsString;
-use std::fs::DirEntry;
+use std::fmt::Display;
+use std::path::PathBuf;
+
+/// Why a candidate was or wasn't suggested, see `Which::suggest_debug`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SuggestionDebug {
+    /// The file name of the candidate
+    pub name: OsString,
+
+    /// Similarity to the program name from 0.0 to 1.0, higher is closer
+    pub score: f64,
+
+    /// Why the candidate was or wasn't suggested
+    pub disposition: SuggestionDisposition,
+}
+
+/// The outcome for a single suggestion candidate
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SuggestionDisposition {
+    /// Returned as a suggestion
+    Suggested,
+
+    /// Has the same name as the program, it's not a different spelling
+    ExactMatch,
+
+    /// A file with the same name was already suggested from an earlier directory
+    Duplicate,
+
+    /// Scored lower than the `guess_limit` closest candidates
+    CutByLimit,
+
+    /// Close enough to suggest, but not a valid executable, see `Which::suggest_executables_only`
+    NotExecutable,
+}
+
+impl Display for SuggestionDisposition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SuggestionDisposition::Suggested => f.write_str("suggested"),
+            SuggestionDisposition::ExactMatch => f.write_str("same name as the program"),
+            SuggestionDisposition::Duplicate => f.write_str("already suggested"),
+            SuggestionDisposition::CutByLimit => f.write_str("cut by the guess limit"),
+            SuggestionDisposition::NotExecutable => f.write_str("not a valid executable"),
+        }
+    }
+}
+
+impl Display for SuggestionDebug {
+    #[allow(clippy::unnecessary_debug_formatting)]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let SuggestionDebug {
+            name,
+            score,
+            disposition,
+        } = self;
+        write!(f, "{score:.3} {name:?} - {disposition}")
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Calls to `spelling` on this thread, so tests can tell when suggestions were computed
+    pub(crate) static SPELLING_CALLS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
 
 /// Find the closest match(es) to the given program name as suggestsions
 ///
 /// Reads in all executables on the PATH and runs a string distance
 /// calculation between them and the `program`.
 ///
-/// The top `guess_limit` results will be returned.
+/// The full paths of the top `guess_limit` results will be returned, closest first
+/// with ties ordered by name. When `file_limit` is set only that many files
+/// are considered, in PATH order. When `executables_only` is true, candidates
+/// that are not valid executables are skipped, which stats each one.
 ///
 /// If no results are found, or `guess_limit` is zero then
 /// None will be returned.
+///
+/// When `debug` is true, every candidate considered is also returned with
+/// its score and why it was or wasn't suggested.
 pub(crate) fn spelling(
     program: &OsString,
-    parts: &[PathPart],
+    listings: &[Listing],
     guess_limit: usize,
-) -> Option<Vec<OsString>> {
+    file_limit: Option<usize>,
+    executables_only: bool,
+    debug: bool,
+) -> (Option<Vec<PathBuf>>, Vec<SuggestionDebug>) {
+    #[cfg(test)]
+    SPELLING_CALLS.with(|calls| calls.set(calls.get() + 1));
+
     if guess_limit == 0 {
-        return None;
+        return (None, Vec::new());
     }
 
-    let mut heap = std::collections::BinaryHeap::new();
-    let values = parts
-        .par_iter()
-        .filter_map(|p| std::fs::read_dir(&p.absolute).ok())
-        .flat_map(|r| {
-            r.filter_map(std::result::Result::ok)
-                .collect::<Vec<DirEntry>>()
-        })
-        .map(|d| d.path())
-        .filter_map(|p| p.file_name().map(std::ffi::OsStr::to_os_string))
-        .map(|filename| {
-            let score = strsim::normalized_levenshtein(
-                &program.to_string_lossy(),
-                &filename.to_string_lossy(),
-            );
-
-            (ordered_float::OrderedFloat(score), filename)
+    let program_lossy = program.to_string_lossy();
+    let mut scored = listings
+        .iter()
+        .flat_map(|listing| listing.names.iter().map(move |name| (&listing.dir, name)))
+        .take(file_limit.unwrap_or(usize::MAX))
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|(dir, name)| {
+            let score = strsim::normalized_levenshtein(&program_lossy, &name.to_string_lossy());
+
+            (ordered_float::OrderedFloat(score), dir, name)
         })
-        .collect::<Vec<(_, _)>>();
+        .collect::<Vec<_>>();
+    // Stable, so candidates with the same name stay in PATH order
+    scored.sort_by(|(a_score, _, a_name), (b_score, _, b_name)| {
+        b_score.cmp(a_score).then_with(|| a_name.cmp(b_name))
+    });
 
-    for value in &values {
-        heap.push(value);
-    }
+    let mut out = Vec::<PathBuf>::new();
+    let mut details = Vec::new();
+    for (score, dir, name) in scored {
+        let disposition = if name == program {
+            SuggestionDisposition::ExactMatch
+        } else if out.iter().any(|path| path.file_name() == Some(name)) {
+            SuggestionDisposition::Duplicate
+        } else if out.len() >= guess_limit {
+            SuggestionDisposition::CutByLimit
+        } else if !executables_only || file_state(&dir.join(name)) == FileState::Valid {
+            SuggestionDisposition::Suggested
+        } else {
+            SuggestionDisposition::NotExecutable
+        };
 
-    if heap.is_empty() {
-        None
-    } else {
-        let mut out = HashSet::new();
-        while let Some((_, filename)) = heap.pop() {
-            if out.len() >= guess_limit {
-                break;
-            }
-            if filename != program {
-                out.insert(filename.clone());
-            }
+        if disposition == SuggestionDisposition::Suggested {
+            out.push(dir.join(name));
         }
-        if out.is_empty() {
-            None
-        } else {
-            Some(out.into_iter().collect_vec())
+        if debug {
+            details.push(SuggestionDebug {
+                name: name.clone(),
+                score: score.into_inner(),
+                disposition,
+            });
+        } else if out.len() >= guess_limit {
+            break;
         }
     }
+
+    if out.is_empty() {
+        (None, details)
+    } else {
+        (Some(out), details)
+    }
+}
+
+/// The file name of each path
+pub(crate) fn file_names(paths: &[PathBuf]) -> Vec<OsString> {
+    paths
+        .iter()
+        .filter_map(|path| path.file_name())
+        .map(OsString::from)
+        .collect()
+}
+
+/// The number of files a suggestion scan stopped at, if the limit was exceeded
+pub(crate) fn truncated_at(listings: &[Listing], file_limit: Option<usize>) -> Option<usize> {
+    let total = listings
+        .iter()
+        .map(|listing| listing.names.len())
+        .sum::<usize>();
+    file_limit.filter(|limit| total > *limit)
+}
+
+/// Find executables whose names start with the program name i.e. `kubectl` for `kube`
+///
+/// Catches a forgotten suffix, which spelling distance ranks poorly for short
+/// names. Results are sorted by name, only the first executable with a given
+/// name on the PATH is returned.
+pub(crate) fn prefixed(program: &OsString, listings: &[Listing]) -> Vec<PathBuf> {
+    let program = program.to_string_lossy();
+    if program.is_empty() {
+        return Vec::new();
+    }
+
+    let mut seen = HashSet::new();
+    listings
+        .iter()
+        .flat_map(|listing| listing.names.iter().map(move |name| (&listing.dir, name)))
+        .filter(|(_, name)| {
+            let name = name.to_string_lossy();
+            name.len() > program.len() && name.starts_with(program.as_ref())
+        })
+        .map(|(dir, name)| dir.join(name))
+        .filter(|path| file_state(path) == FileState::Valid)
+        .filter(|path| seen.insert(path.file_name().map(OsString::from)))
+        .sorted_by(|a, b| a.file_name().cmp(&b.file_name()))
+        .collect()
+}
+
+/// Find executables whose names only differ from the program by hyphens and underscores
+///
+/// i.e. `docker-compose` for `docker_compose` or `dockercompose`. Results are sorted
+/// by name, only the first executable with a given name on the PATH is returned.
+pub(crate) fn separator_variants(program: &OsString, listings: &[Listing]) -> Vec<PathBuf> {
+    let key = without_separators(&program.to_string_lossy());
+    if key.is_empty() {
+        return Vec::new();
+    }
+
+    let mut seen = HashSet::new();
+    listings
+        .iter()
+        .flat_map(|listing| listing.names.iter().map(move |name| (&listing.dir, name)))
+        .filter(|(_, name)| *name != program)
+        .filter(|(_, name)| without_separators(&name.to_string_lossy()) == key)
+        .map(|(dir, name)| dir.join(name))
+        .filter(|path| file_state(path) == FileState::Valid)
+        .filter(|path| seen.insert(path.file_name().map(OsString::from)))
+        .sorted_by(|a, b| a.file_name().cmp(&b.file_name()))
+        .collect()
+}
+
+fn without_separators(name: &str) -> String {
+    name.chars().filter(|c| !matches!(c, '-' | '_')).collect()
+}
+
+/// An executable following the `<command><separator><program>` subcommand convention
+///
+/// For example `cargo-foo` is run as `cargo foo`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Subcommand {
+    pub(crate) command: OsString,
+    pub(crate) program: OsString,
+    pub(crate) path: PathBuf,
+}
+
+impl Display for Subcommand {
+    #[allow(clippy::unnecessary_debug_formatting)]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Subcommand {
+            command,
+            program,
+            path,
+        } = self;
+        let name = path.file_name().unwrap_or(path.as_os_str());
+        write!(
+            f,
+            "{program:?} may be a subcommand. Found {name:?}, try `{} {}`",
+            command.to_string_lossy(),
+            program.to_string_lossy()
+        )
+    }
+}
+
+/// Find executables named `<command><separator><program>`
+///
+/// Results are sorted by name, only the first executable with a given name
+/// on the PATH is returned.
+pub(crate) fn subcommands(
+    program: &OsString,
+    listings: &[Listing],
+    separator: &str,
+) -> Vec<Subcommand> {
+    let suffix = format!("{separator}{}", program.to_string_lossy());
+    let mut seen = HashSet::new();
+    listings
+        .iter()
+        .flat_map(|listing| {
+            listing
+                .names
+                .iter()
+                .map(move |name| (listing.dir.join(name), name.to_string_lossy()))
+        })
+        .filter_map(|(path, name)| {
+            name.strip_suffix(&suffix)
+                .filter(|command| !command.is_empty())
+                .map(|command| (path, OsString::from(command)))
+        })
+        .filter(|(path, _)| file_state(path) == FileState::Valid)
+        .filter(|(_, command)| seen.insert(command.clone()))
+        .map(|(path, command)| Subcommand {
+            command,
+            program: program.clone(),
+            path,
+        })
+        .sorted_by(|a, b| a.command.cmp(&b.command))
+        .collect()
 }
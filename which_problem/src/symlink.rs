@@ -0,0 +1,47 @@
+use std::path::{Path, PathBuf};
+
+/// Same as the Linux kernel limit `MAXSYMLINKS`
+pub(crate) const MAX_HOPS: usize = 40;
+
+/// Follow a symlink one hop at a time
+///
+/// Returns every path in the chain starting with `path` itself. The last
+/// element is the first path that is not a symlink (or cannot be read).
+/// Stops after `max_hops` hops.
+pub(crate) fn chain(path: &Path, max_hops: usize) -> Vec<PathBuf> {
+    let mut chain = vec![path.to_path_buf()];
+    let mut current = path.to_path_buf();
+    while chain.len() <= max_hops {
+        let target = match std::fs::read_link(&current) {
+            // Relative targets are relative to the directory holding the link
+            Ok(target) => match current.parent() {
+                Some(parent) => parent.join(target),
+                None => target,
+            },
+            Err(_) => break,
+        };
+        chain.push(target.clone());
+        current = target;
+    }
+    chain
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_relative_links() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("real");
+        std::fs::write(&file, "contents").unwrap();
+        std::os::unix::fs::symlink("real", dir.join("one")).unwrap();
+        std::os::unix::fs::symlink(dir.join("one"), dir.join("two")).unwrap();
+
+        assert_eq!(
+            vec![dir.join("two"), dir.join("one"), file],
+            chain(&dir.join("two"), MAX_HOPS)
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use crate::program::Program;
+use std::fmt::Write;
+
+impl Program {
+    /// Render found files and PATH parts as aligned columns with a header row
+    ///
+    /// A lighter alternative to the `Display` output. The first column marks
+    /// the file that will run, and the directory it was found in, with `>`.
+    #[must_use]
+    pub fn render_compact_table(&self) -> String {
+        let mut out = String::new();
+        // Writing to a String cannot fail
+        let _ = self.write_compact_table(&mut out);
+        out
+    }
+
+    #[allow(clippy::unnecessary_debug_formatting)]
+    fn write_compact_table(&self, f: &mut dyn Write) -> std::fmt::Result {
+        let name = &self.name;
+        let executable = self.executable();
+        match executable {
+            Some(found) => writeln!(f, "Program {name:?} found at {:?}", found.path)?,
+            None => writeln!(f, "Program {name:?} not found")?,
+        }
+
+        if !self.found_files.is_empty() {
+            let width = self
+                .found_files
+                .iter()
+                .map(|p| p.state.to_string().len())
+                .chain(Some("STATE".len()))
+                .max()
+                .unwrap_or_default();
+
+            f.write_char('\n')?;
+            writeln!(f, "  {:width$}  FILE", "STATE")?;
+            for file in &self.found_files {
+                let marker = if Some(file) == executable { '>' } else { '-' };
+                writeln!(
+                    f,
+                    "{marker} {:width$}  {:?}",
+                    file.state.to_string(),
+                    file.path
+                )?;
+            }
+        }
+
+        if !self.path_parts.is_empty() {
+            let width = self
+                .path_parts
+                .iter()
+                .map(|p| p.state.to_string().len())
+                .chain(Some("STATE".len()))
+                .max()
+                .unwrap_or_default();
+            let winning_dir = executable.and_then(|found| found.path.parent());
+
+            f.write_char('\n')?;
+            writeln!(f, "  {:width$}  DIRECTORY", "STATE")?;
+            for part in &self.path_parts {
+                let marker = if winning_dir == Some(part.absolute.as_path()) {
+                    '>'
+                } else {
+                    '-'
+                };
+                write!(
+                    f,
+                    "{marker} {:width$}  {:?}",
+                    part.state.to_string(),
+                    part.original
+                )?;
+                if part.relative {
+                    write!(f, " (relative from {:?})", part.cwd)?;
+                }
+                f.write_char('\n')?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use crate::file_state::FileState;
+use crate::program::Program;
+use std::fmt::Write;
+use std::path::Path;
+
+impl Program {
+    /// Render found files, PATH parts, and suggestions as tab-separated values
+    ///
+    /// Starts with a `kind path state note` header, then one row per found
+    /// file (`file`), PATH part in PATH order (`path`), and suggestion
+    /// (`suggestion`). Tabs, newlines, and backslashes in values are escaped
+    /// as `\t`, `\n`, and `\\` so every row has exactly four columns.
+    #[must_use]
+    pub fn render_tsv(&self) -> String {
+        let mut out = String::new();
+        // Writing to a String cannot fail
+        let _ = self.write_tsv(&mut out);
+        out
+    }
+
+    #[allow(clippy::unnecessary_debug_formatting)]
+    fn write_tsv(&self, f: &mut dyn Write) -> std::fmt::Result {
+        writeln!(f, "kind\tpath\tstate\tnote")?;
+
+        let executable = self.executable().map(|found| &found.path);
+        for file in &self.found_files {
+            let note = if Some(&file.path) == executable {
+                "will run".to_string()
+            } else if file.state == FileState::Valid {
+                "shadowed".to_string()
+            } else {
+                file.state.details()
+            };
+            row(f, "file", &file.path, &file.state.to_string(), &note)?;
+        }
+
+        for part in &self.path_parts {
+            let note = match &part.hint {
+                Some(hint) => hint.to_string(),
+                None if part.relative => format!("relative, resolves to {:?}", part.absolute),
+                None => String::new(),
+            };
+            row(f, "path", &part.original, &part.state.to_string(), &note)?;
+        }
+
+        for path in self.suggestions_with_paths() {
+            row(f, "suggestion", &path, &FileState::Valid.to_string(), "")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn row(f: &mut dyn Write, kind: &str, path: &Path, state: &str, note: &str) -> std::fmt::Result {
+    writeln!(
+        f,
+        "{kind}\t{}\t{}\t{}",
+        escape(&path.to_string_lossy()),
+        escape(state),
+        escape(note)
+    )
+}
+
+/// Escape characters that would break the row and column layout
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_separators() {
+        assert_eq!(r"a\tb\nc\\d", escape("a\tb\nc\\d"));
+    }
+}
@@ -0,0 +1,173 @@
+use crate::program::Program;
+use crate::which::Which;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::JoinHandle;
+
+/// Watches the PATH directories of a `Which` for changes, returned by `Which::watch`
+///
+/// Stops watching when dropped.
+pub struct WhichWatcher {
+    sender: Sender<Message>,
+    thread: Option<JoinHandle<()>>,
+}
+
+enum Message {
+    Changed,
+    Stop,
+}
+
+impl Drop for WhichWatcher {
+    fn drop(&mut self) {
+        let _ = self.sender.send(Message::Stop);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Which {
+    /// Diagnose again whenever a PATH directory changes, i.e. a program is installed or removed
+    ///
+    /// The callback is called with the fresh `Program` from a background thread
+    /// after each change, several changes in quick succession are diagnosed once.
+    /// A directory that doesn't exist yet is watched through its nearest existing
+    /// parent, so it's picked up once it's created. Diagnoses that fail, i.e.
+    /// because the `cwd` was removed, are skipped.
+    ///
+    /// Example:
+    ///
+    /// ```rust,no_run
+    /// use which_problem::Which;
+    ///
+    /// let _watcher = Which::new("bundle")
+    ///     .watch(|program| eprintln!("{program}"))
+    ///     .unwrap();
+    /// // Changes are reported until `_watcher` is dropped
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// - If the current directory cannot be determined
+    /// - If the platform's file watcher cannot be started
+    pub fn watch<F>(&self, callback: F) -> Result<WhichWatcher, std::io::Error>
+    where
+        F: FnMut(Program) + Send + 'static,
+    {
+        let cwd = self.current_dir()?;
+        let (sender, receiver) = mpsc::channel();
+        let events = sender.clone();
+        let mut watcher = notify::recommended_watcher(move |_| {
+            let _ = events.send(Message::Changed);
+        })
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error))?;
+
+        let which = Which {
+            cwd: Some(cwd.clone()),
+            ..self.clone()
+        };
+        let mut watched_dirs = HashSet::new();
+        update_watches(&mut watcher, &mut watched_dirs, &which.dirs(&cwd));
+
+        let thread = std::thread::spawn(move || {
+            run(&which, &cwd, &receiver, watcher, watched_dirs, callback);
+        });
+
+        Ok(WhichWatcher {
+            sender,
+            thread: Some(thread),
+        })
+    }
+}
+
+fn run<F: FnMut(Program)>(
+    which: &Which,
+    cwd: &Path,
+    receiver: &Receiver<Message>,
+    mut watcher: RecommendedWatcher,
+    mut watched_dirs: HashSet<PathBuf>,
+    mut callback: F,
+) {
+    while let Ok(Message::Changed) = receiver.recv() {
+        // Handle a burst of changes once
+        loop {
+            match receiver.try_recv() {
+                Ok(Message::Changed) => {}
+                Ok(Message::Stop) => return,
+                Err(_) => break,
+            }
+        }
+
+        // Watch directories created since the last change before diagnosing,
+        // so anything added to them afterwards is still reported
+        update_watches(&mut watcher, &mut watched_dirs, &which.dirs(cwd));
+        if let Ok(program) = which.diagnose() {
+            callback(program);
+        }
+    }
+}
+
+/// Watch each directory, or its nearest existing parent when it doesn't exist
+fn update_watches(
+    watcher: &mut RecommendedWatcher,
+    watched_dirs: &mut HashSet<PathBuf>,
+    dirs: &[PathBuf],
+) {
+    let wanted = dirs
+        .iter()
+        .filter_map(|dir| dir.ancestors().find(|ancestor| ancestor.is_dir()))
+        .map(Path::to_path_buf)
+        .collect::<HashSet<_>>();
+
+    for path in watched_dirs.difference(&wanted) {
+        let _ = watcher.unwatch(path);
+    }
+    watched_dirs.retain(|path| wanted.contains(path));
+    for path in wanted {
+        if !watched_dirs.contains(&path)
+            && watcher.watch(&path, RecursiveMode::NonRecursive).is_ok()
+        {
+            watched_dirs.insert(path);
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn watch_reports_program_installed_in_missing_dir() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let bin = tmp_dir.path().join("bin");
+        let (sender, receiver) = mpsc::channel();
+
+        let _watcher = Which {
+            path_env: Some(bin.clone().into_os_string()),
+            ..Which::new("lol")
+        }
+        .watch(move |program| {
+            let _ = sender.send(program);
+        })
+        .unwrap();
+
+        std::fs::create_dir(&bin).unwrap();
+        let file = bin.join("lol");
+        std::fs::write(&file, "contents").unwrap();
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let found = std::iter::from_fn(|| {
+            receiver
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .ok()
+        })
+        .find_map(|program| program.executable().map(|found| found.path.clone()));
+
+        assert_eq!(Some(file), found);
+    }
+}
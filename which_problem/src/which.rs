@@ -1,10 +1,25 @@
-use crate::file_state::FileState;
-use crate::path_part::PathPart;
-use crate::path_with_state::PathWithState;
+use crate::explicit::{self, ExplicitPath};
+use crate::file_state::{bounded_file_state, file_state, FileState};
+use crate::listing::{self, DirCache, Listing};
+use crate::normalize::normalize;
+use crate::path_diff::{self, PathDiff};
+use crate::path_part::{self, PartState, PathPart};
+use crate::path_with_state::{PathWithState, Permissions};
+use crate::problem::Problem;
 use crate::program::Program;
+use crate::shared_library;
+use crate::sudo::{self, SecurePath, UnderSudo};
 use crate::suggest;
+use itertools::Itertools;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
-use std::{ffi::OsString, path::PathBuf};
+use std::fmt::Display;
+use std::time::Duration;
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
 
 /// Find problems with executable lookup
 ///
@@ -32,21 +47,193 @@ use std::{ffi::OsString, path::PathBuf};
 /// eprintln!("{}", which.diagnose().unwrap());
 /// ```
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+// Each flag is an independent opt-in check
+#[allow(clippy::struct_excessive_bools)]
 pub struct Which {
-    /// The current working directory, affects PATHs with relative parts
+    /// The current working directory, affects PATHs with relative parts.
+    /// Must be a directory.
+    ///
+    /// Default: None, uses `std::env::current_dir()`
     pub cwd: Option<PathBuf>,
 
     /// The program name you're trying to execute i.e.
     /// for `bundle install` would be "bundle".
+    ///
+    /// A program containing a directory i.e. "/opt/x/bin/x" or "./bin/x" is
+    /// checked directly, relative to the `cwd`, instead of being searched for
+    /// on the PATH. The PATH is still analyzed.
+    ///
+    /// Default: empty, must be set
     pub program: OsString,
 
     /// The contents of PATH environment variable
-    /// i.e. OsString::new("different:path:here")
+    /// i.e. `OsString::new("different:path:here")`. None when PATH is not set.
+    ///
+    /// Default: the PATH of the current process
     pub path_env: Option<OsString>,
 
     /// How many guesses to suggest if the command could not be found
     /// set to 0 to disable.
+    ///
+    /// Default: 3
     pub guess_limit: usize,
+
+    /// Also diagnose the program against the PATH used by `sudo`
+    /// i.e. `Some(SecurePath::default())` reads `secure_path` from `/etc/sudoers`
+    ///
+    /// Default: None, disabled
+    pub sudo: Option<SecurePath>,
+
+    /// When the program is not found, look for subcommand style executables
+    /// named `<command><separator><program>` i.e. with `Some("-".to_string())`
+    /// a program "foo" will suggest `cargo foo` if `cargo-foo` is on the PATH.
+    ///
+    /// Default: None, disabled
+    pub subcommand_separator: Option<String>,
+
+    /// Directories the program is allowed to resolve from i.e. `/usr/bin`.
+    /// When set, a program that resolves from any other directory is reported
+    /// as a problem. Relative directories are relative to the `cwd`.
+    ///
+    /// Default: None, any directory is allowed
+    pub allowed_dirs: Option<Vec<PathBuf>>,
+
+    /// Stop scoring suggestions after this many files, across all PATH directories
+    /// in PATH order. Bounds the time spent on suggestions when the PATH holds many
+    /// large directories.
+    ///
+    /// Default: None, every file is considered
+    pub suggest_total_file_limit: Option<usize>,
+
+    /// Compare the modification times of the valid executables found and report
+    /// when the one that will run is much older than a copy later on the PATH,
+    /// a hint that a stale build is shadowing a newer one. Stats every match.
+    ///
+    /// Default: false, disabled
+    pub check_staleness: bool,
+
+    /// Record the score of every suggestion candidate and why it was or wasn't
+    /// suggested, available from `Program::suggestion_debug()`.
+    ///
+    /// Default: false, disabled
+    pub suggest_debug: bool,
+
+    /// Only suggest valid executables. Each candidate close enough to be
+    /// suggested is checked on disk, and skipped when it can't run.
+    ///
+    /// Default: false, any file on the PATH can be suggested
+    pub suggest_executables_only: bool,
+
+    /// When the whole PATH is wrapped in matching quotes i.e. `"/usr/bin:/bin"`,
+    /// which is always reported, diagnose it with the quotes removed.
+    ///
+    /// Default: false, the quotes are treated as part of the first and last directories
+    pub unquote_path: bool,
+
+    /// Report which directories of the diagnosed PATH were added or removed
+    /// relative to the PATH of the current process, useful when `path_env`
+    /// is assembled separately i.e. in CI.
+    ///
+    /// Default: false, disabled
+    pub compare_process_path: bool,
+
+    /// Show the permission bits and owner of each file found i.e. `(mode 0644, owned by root:staff)`,
+    /// which makes a missing executable bit obvious. Only has an effect on unix.
+    ///
+    /// Default: false, disabled
+    pub show_permissions: bool,
+
+    /// Directories that are expected to be empty i.e. a per-project `bin` that is
+    /// populated later. When one of these is empty on the PATH it's still shown
+    /// as empty, but it's not reported as a problem. Relative directories are
+    /// relative to the `cwd`.
+    ///
+    /// Default: empty, every empty directory is reported
+    pub ignore_empty_dirs: Vec<PathBuf>,
+
+    /// Project local directories that tools are installed into, but that are
+    /// usually not on the PATH. When the program is not found, each of these
+    /// is checked relative to the `cwd` and then every parent directory, the
+    /// same way `npx` finds `node_modules/.bin`.
+    ///
+    /// Default: `node_modules/.bin`
+    pub local_bin_dirs: Vec<PathBuf>,
+
+    /// Extensions to try after the program name in each directory, separated
+    /// by `;` i.e. `.COM;.EXE;.BAT`. On Windows, names and extensions are matched
+    /// ignoring case, and a program found only by ignoring case is noted.
+    ///
+    /// Default: the `PATHEXT` of the current process on Windows, None elsewhere
+    pub pathext: Option<OsString>,
+
+    /// Follow symlinks to the program one hop at a time, at most this many hops,
+    /// instead of resolving them with `canonicalize` which can block on an
+    /// automount or slow network filesystem. A longer chain is reported as
+    /// `FileState::TooManySymlinkHops`.
+    ///
+    /// Default: None, symlinks are resolved by `canonicalize`
+    pub max_symlink_hops: Option<usize>,
+
+    /// Warn when both a `.cmd` or `.bat` script wrapper and a native `.exe` are
+    /// found for the program, explaining which one runs. On Windows the wrapper
+    /// runs through `cmd.exe`, which changes how arguments and exit codes are handled.
+    ///
+    /// Default: false, disabled
+    pub check_script_wrappers: bool,
+
+    /// Run `/usr/bin/ldd` on the program that will run to find shared libraries the
+    /// dynamic loader can't find, which make a valid executable fail to start.
+    /// Only has an effect on Linux. Some versions of `ldd` may execute the
+    /// binary, only enable this for programs you trust.
+    ///
+    /// Default: false, disabled
+    pub check_shared_libraries: bool,
+}
+
+/// How much older the winning executable must be before it's reported as stale
+const STALENESS_THRESHOLD: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Larger values are likely a mistake, the suggestions would be noise
+const MAX_REASONABLE_GUESS_LIMIT: usize = 100;
+
+/// Suspicious `Which` configuration found by `Which::validate`
+///
+/// These are advisory, `Which::diagnose` still runs with them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WhichConfigWarning {
+    /// `cwd` is set to something that is not a directory
+    CwdNotDir(PathBuf),
+
+    /// `guess_limit` is so large the suggestions would not be useful
+    GuessLimitTooLarge(usize),
+
+    /// `program` is empty, but other options were configured
+    BlankProgram,
+
+    /// `subcommand_separator` is empty, every executable ending in the program name would match
+    EmptySubcommandSeparator,
+}
+
+impl Display for WhichConfigWarning {
+    #[allow(clippy::unnecessary_debug_formatting)]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WhichConfigWarning::CwdNotDir(path) => {
+                write!(f, "The cwd {path:?} is not a directory")
+            }
+            WhichConfigWarning::GuessLimitTooLarge(limit) => write!(
+                f,
+                "The guess_limit {limit} is larger than {MAX_REASONABLE_GUESS_LIMIT}"
+            ),
+            WhichConfigWarning::BlankProgram => {
+                f.write_str("The program is blank, but other options are set")
+            }
+            WhichConfigWarning::EmptySubcommandSeparator => {
+                f.write_str("The subcommand_separator is empty")
+            }
+        }
+    }
 }
 
 impl Which {
@@ -58,35 +245,384 @@ impl Which {
         }
     }
 
-    fn resolve(&self) -> Result<ResolvedWhich, std::io::Error> {
+    /// Check for suspicious configuration before running `diagnose`
+    ///
+    /// Only inspects the struct and the `cwd` on disk.
+    ///
+    /// # Errors
+    ///
+    /// - Returns every configuration warning found
+    pub fn validate(&self) -> Result<(), Vec<WhichConfigWarning>> {
+        let mut warnings = Vec::new();
+        if let Some(cwd) = self.cwd.as_ref().filter(|cwd| !cwd.is_dir()) {
+            warnings.push(WhichConfigWarning::CwdNotDir(cwd.clone()));
+        }
+        if self.guess_limit > MAX_REASONABLE_GUESS_LIMIT {
+            warnings.push(WhichConfigWarning::GuessLimitTooLarge(self.guess_limit));
+        }
+        if self.program.is_empty() && self != &Which::default() {
+            warnings.push(WhichConfigWarning::BlankProgram);
+        }
+        if self.subcommand_separator.as_deref() == Some("") {
+            warnings.push(WhichConfigWarning::EmptySubcommandSeparator);
+        }
+
+        if warnings.is_empty() {
+            Ok(())
+        } else {
+            Err(warnings)
+        }
+    }
+
+    /// The PATH to search, with the quotes removed when `unquote_path` is set
+    fn path_env(&self) -> Option<OsString> {
+        match self.path_env.as_deref().and_then(unquoted) {
+            Some(inner) if self.unquote_path => Some(inner),
+            _ => self.path_env.clone(),
+        }
+    }
+
+    /// The file names looked for in each PATH directory, in the order they are tried
+    ///
+    /// The program, followed by the program with each extension from `pathext`
+    /// i.e. `["tool", "tool.exe", "tool.cmd"]` on Windows. On Windows these are
+    /// also matched ignoring case.
+    ///
+    /// Example:
+    ///
+    /// ```rust
+    /// use std::ffi::OsString;
+    /// use which_problem::Which;
+    ///
+    /// let which = Which {
+    ///     pathext: Some(OsString::from(".sh")),
+    ///     ..Which::new("tool")
+    /// };
+    /// assert_eq!(
+    ///     vec![OsString::from("tool"), OsString::from("tool.sh")],
+    ///     which.program_variants()
+    /// );
+    /// ```
+    #[must_use]
+    pub fn program_variants(&self) -> Vec<OsString> {
+        variants(&self.program, self.pathext.as_deref(), FOLD_CASE)
+    }
+
+    pub(crate) fn current_dir(&self) -> Result<PathBuf, std::io::Error> {
+        match self.cwd.clone() {
+            Some(path) => Ok(path),
+            None => std::env::current_dir(),
+        }
+    }
+
+    /// The directory of a program given as a path, as written
+    fn explicit_original(&self) -> Option<PathBuf> {
+        explicit::is_explicit(&self.program).then(|| {
+            Path::new(&self.program)
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default()
+        })
+    }
+
+    /// Absolute directories that need to be read to diagnose the program
+    pub(crate) fn dirs(&self, cwd: &Path) -> Vec<PathBuf> {
+        // Joining an absolute path replaces the cwd
+        self.path_env()
+            .iter()
+            .flat_map(std::env::split_paths)
+            .chain(self.explicit_original())
+            .map(|part| cwd.join(part))
+            .collect()
+    }
+
+    fn resolve(&self, cache: &mut DirCache) -> Result<ResolvedWhich, std::io::Error> {
+        let cwd = self.current_dir()?;
+        listing::fill(cache, self.dirs(&cwd).iter().map(PathBuf::as_path));
+
+        Ok(self.resolve_cached(&cwd, cache))
+    }
+
+    /// Same as `resolve`, directories missing from the cache are treated as unreadable
+    fn resolve_cached(&self, cwd: &Path, cache: &DirCache) -> ResolvedWhich {
         let program = self.program.clone();
-        let path_env = self.path_env.clone().unwrap_or_else(|| OsString::from(""));
+        let names = self.program_variants();
+        let path_unset = self.path_env.is_none();
+        let quoted_path = self.path_env.as_deref().and_then(unquoted).is_some();
+        let path_env = self.path_env();
+        let cwd = cwd.to_path_buf();
 
-        let cwd = match self.cwd.clone() {
-            Some(path) => path,
-            None => std::env::current_dir()?,
-        };
+        let originals = path_env
+            .iter()
+            .flat_map(std::env::split_paths)
+            .collect::<Vec<_>>();
+        let explicit_original = self.explicit_original();
 
-        let path_parts = std::env::split_paths(&path_env.as_os_str())
-            .map(|part| PathPart::new(&cwd, &part))
+        let ignore_empty_dirs = self
+            .ignore_empty_dirs
+            .iter()
+            .map(|dir| normalize(&cwd.join(dir)))
+            .collect::<HashSet<_>>();
+        let path_parts = originals
+            .iter()
+            .map(|part| PathPart::new(&cwd, part, cache))
+            .map(|mut part| {
+                part.expected_empty = part.state == PartState::EmptyDir
+                    && ignore_empty_dirs.contains(&part.normalized);
+                part
+            })
             .collect::<Vec<_>>();
+        let listings = listing::from_cache(cache, &path_parts);
+        let process_path_diff = self.compare_process_path.then(|| {
+            path_diff::diff(
+                &cwd,
+                path_env.as_deref(),
+                std::env::var_os("PATH").as_deref(),
+            )
+        });
+        let explicit_dir = explicit_original.map(|part| PathPart::new(&cwd, &part, cache));
+        let explicit_listings = listing::from_cache(cache, &explicit_dir);
 
         let guess_limit = self.guess_limit;
+        let subcommand_separator = self.subcommand_separator.clone();
+        let allowed_dirs = self.allowed_dirs.as_ref().map(|dirs| {
+            dirs.iter()
+                .map(|dir| normalize(&cwd.join(dir)))
+                .collect::<Vec<_>>()
+        });
 
-        Ok(ResolvedWhich {
+        ResolvedWhich {
             program,
+            names,
+            path_unset,
             path_parts,
+            listings,
             guess_limit,
-        })
+            subcommand_separator,
+            allowed_dirs,
+            suggest_total_file_limit: self.suggest_total_file_limit,
+            check_staleness: self.check_staleness,
+            suggest_debug: self.suggest_debug,
+            suggest_executables_only: self.suggest_executables_only,
+            quoted_path,
+            unquote_path: self.unquote_path,
+            process_path_diff,
+            show_permissions: self.show_permissions,
+            local_bin_dirs: self.local_bin_dirs.clone(),
+            max_symlink_hops: self.max_symlink_hops,
+            check_script_wrappers: self.check_script_wrappers,
+            check_shared_libraries: self.check_shared_libraries,
+            cwd,
+            explicit_dir,
+            explicit_listings,
+        }
+    }
+
+    /// The path `Command::new(program)` will execute, or None if it's not found
+    ///
+    /// Only searches the PATH in order and stops at the first valid executable.
+    /// Does not collect suggestions or PATH details, use `diagnose` for those.
+    ///
+    /// Example:
+    ///
+    /// ```rust,no_run
+    /// use which_problem::Which;
+    ///
+    /// if let Some(path) = Which::new("bundle").which().unwrap() {
+    ///     println!("bundle resolves to {}", path.display());
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// - If the current directory cannot be determined
+    pub fn which(&self) -> Result<Option<PathBuf>, std::io::Error> {
+        let cwd = self.current_dir()?;
+
+        if explicit::is_explicit(&self.program) {
+            let path = cwd.join(&self.program);
+            return Ok(
+                (bounded_file_state(&path, self.max_symlink_hops) == FileState::Valid)
+                    .then_some(path),
+            );
+        }
+
+        let names = self.program_variants();
+        Ok(self
+            .path_env()
+            .iter()
+            .flat_map(std::env::split_paths)
+            .map(|part| cwd.join(part))
+            .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+            .find(|path| bounded_file_state(path, self.max_symlink_hops) == FileState::Valid))
     }
 
     /// # Errors
     ///
     /// - If the current directory cannot be determined
     pub fn diagnose(&self) -> Result<Program, std::io::Error> {
-        let which = self.resolve()?;
+        self.diagnose_with_cache(&mut HashMap::new())
+    }
+
+    /// Same as `diagnose`, but reuses directory contents from a caller owned cache
+    ///
+    /// The cache maps absolute PATH directories to the names of their entries.
+    /// Directories missing from the cache are read and added to it, so the
+    /// same cache can be passed to later calls to avoid reading them again.
+    ///
+    /// Entries in the cache are trusted as-is. If a directory changes on disk
+    /// after it's cached (i.e. a program is installed) the results will be stale
+    /// until its entry is removed or the cache is cleared. The caller decides how
+    /// long entries live.
+    ///
+    /// # Errors
+    ///
+    /// - If the current directory cannot be determined
+    pub fn diagnose_with_cache(
+        &self,
+        cache: &mut HashMap<PathBuf, Vec<OsString>>,
+    ) -> Result<Program, std::io::Error> {
+        let which = self.resolve(cache)?;
+        let mut program = which.check();
+        if let Some(secure_path) = &self.sudo {
+            program.sudo = Some(self.diagnose_sudo(secure_path, cache)?);
+        }
+
+        Ok(program)
+    }
+
+    /// The most severe problem, or None if nothing is wrong
+    ///
+    /// Same as the first of `diagnose()?.problems()`, but checks run in
+    /// severity order and stop at the first problem found, i.e. a program that
+    /// is not found is reported without looking at the PATH parts. Spelling
+    /// suggestions, subcommands, and the `sudo` diagnosis never add a problem
+    /// and are skipped.
+    ///
+    /// Example:
+    ///
+    /// ```rust,no_run
+    /// use which_problem::Which;
+    ///
+    /// if let Some(problem) = Which::new("bundle").first_problem().unwrap() {
+    ///     eprintln!("Problem: {problem}");
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// - If the current directory cannot be determined
+    pub fn first_problem(&self) -> Result<Option<Problem>, std::io::Error> {
+        Ok(self.resolve(&mut DirCache::new())?.first_problem())
+    }
+
+    /// Diagnose several programs against the same PATH in parallel
+    ///
+    /// Every directory is read once, in parallel, then each program is checked
+    /// against the shared directory contents in parallel. Results are in the
+    /// same order as `programs` and match calling `diagnose` on each one.
+    /// The `program` field of `self` is ignored.
+    ///
+    /// Example:
+    ///
+    /// ```rust,no_run
+    /// use which_problem::Which;
+    ///
+    /// for program in Which::default()
+    ///     .diagnose_parallel_programs(&["bundle", "ruby", "gem"])
+    ///     .unwrap()
+    /// {
+    ///     eprintln!("{program}");
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// - If the current directory cannot be determined
+    pub fn diagnose_parallel_programs<S: AsRef<OsStr>>(
+        &self,
+        programs: &[S],
+    ) -> Result<Vec<Program>, std::io::Error> {
+        self.diagnose_parallel_programs_with(programs, listing::read_dir)
+    }
+
+    /// Same as `diagnose_parallel_programs`, but reads each PATH directory with `read`
+    pub(crate) fn diagnose_parallel_programs_with<S, R>(
+        &self,
+        programs: &[S],
+        read: R,
+    ) -> Result<Vec<Program>, std::io::Error>
+    where
+        S: AsRef<OsStr>,
+        R: Fn(&Path) -> Option<Vec<OsString>> + Sync,
+    {
+        let cwd = self.current_dir()?;
+        let whiches = programs
+            .iter()
+            .map(|program| Which {
+                program: program.as_ref().into(),
+                ..self.clone()
+            })
+            .collect::<Vec<_>>();
+
+        let mut cache = DirCache::new();
+        let dirs = whiches
+            .iter()
+            .flat_map(|which| which.dirs(&cwd))
+            .collect::<Vec<_>>();
+        listing::fill_with(&mut cache, dirs.iter().map(PathBuf::as_path), read);
+
+        let mut diagnosed = whiches
+            .par_iter()
+            .map(|which| which.resolve_cached(&cwd, &cache).check())
+            .collect::<Vec<_>>();
+
+        if let Some(secure_path) = &self.sudo {
+            for (which, program) in whiches.iter().zip(diagnosed.iter_mut()) {
+                program.sudo = Some(which.diagnose_sudo(secure_path, &mut cache)?);
+            }
+        }
+
+        Ok(diagnosed)
+    }
 
-        Ok(which.check())
+    #[allow(clippy::unnecessary_debug_formatting)]
+    fn diagnose_sudo(
+        &self,
+        secure_path: &SecurePath,
+        cache: &mut DirCache,
+    ) -> Result<UnderSudo, std::io::Error> {
+        let secure_path = match secure_path {
+            SecurePath::Explicit(path) => path.clone(),
+            SecurePath::Sudoers(sudoers) => match std::fs::read_to_string(sudoers) {
+                Ok(contents) => match sudo::parse_secure_path(&contents) {
+                    Some(path) => path,
+                    None => {
+                        return Ok(UnderSudo::Unknown(format!(
+                            "No `secure_path` set in {sudoers:?}, sudo may use the same PATH"
+                        )))
+                    }
+                },
+                Err(error) => {
+                    return Ok(UnderSudo::Unknown(format!(
+                        "Could not read {sudoers:?} to find the PATH used by sudo (`secure_path`), \
+                        reading it usually requires root. Error: {error}"
+                    )))
+                }
+            },
+        };
+
+        let program = Which {
+            path_env: Some(secure_path.clone()),
+            sudo: None,
+            ..self.clone()
+        }
+        .diagnose_with_cache(cache)?;
+
+        Ok(UnderSudo::Diagnosed {
+            secure_path,
+            program: Box::new(program),
+        })
     }
 }
 
@@ -97,32 +633,701 @@ impl Default for Which {
             path_env: std::env::var_os("PATH"),
             guess_limit: 3,
             cwd: None,
+            sudo: None,
+            subcommand_separator: None,
+            allowed_dirs: None,
+            suggest_total_file_limit: None,
+            check_staleness: false,
+            suggest_debug: false,
+            suggest_executables_only: false,
+            unquote_path: false,
+            compare_process_path: false,
+            show_permissions: false,
+            ignore_empty_dirs: Vec::new(),
+            local_bin_dirs: vec![PathBuf::from("node_modules").join(".bin")],
+            pathext: if cfg!(windows) {
+                std::env::var_os("PATHEXT")
+            } else {
+                None
+            },
+            max_symlink_hops: None,
+            check_script_wrappers: false,
+            check_shared_libraries: false,
         }
     }
 }
 
+impl From<&str> for Which {
+    fn from(program: &str) -> Self {
+        Which::new(program)
+    }
+}
+
+impl From<String> for Which {
+    fn from(program: String) -> Self {
+        Which::new(program)
+    }
+}
+
+impl From<&OsStr> for Which {
+    fn from(program: &OsStr) -> Self {
+        Which::new(program)
+    }
+}
+
+// Flags are copied from `Which` as-is
+#[allow(clippy::struct_excessive_bools)]
 struct ResolvedWhich {
     program: OsString,
+    /// File names to look for on the PATH, see `variants`
+    names: Vec<OsString>,
+    path_unset: bool,
     path_parts: Vec<PathPart>,
+    listings: Vec<Listing>,
     guess_limit: usize,
+    subcommand_separator: Option<String>,
+    allowed_dirs: Option<Vec<PathBuf>>,
+    suggest_total_file_limit: Option<usize>,
+    check_staleness: bool,
+    suggest_debug: bool,
+    suggest_executables_only: bool,
+    quoted_path: bool,
+    unquote_path: bool,
+    process_path_diff: Option<PathDiff>,
+    show_permissions: bool,
+    local_bin_dirs: Vec<PathBuf>,
+    max_symlink_hops: Option<usize>,
+    check_script_wrappers: bool,
+    check_shared_libraries: bool,
+    cwd: PathBuf,
+    explicit_dir: Option<PathPart>,
+    explicit_listings: Vec<Listing>,
 }
 
 impl ResolvedWhich {
+    /// Files matching the program, an explicit program is only looked for in its own directory
+    fn found_files(
+        &self,
+        explicit: Option<&ExplicitPath>,
+        listings: &[Listing],
+    ) -> Vec<PathWithState> {
+        match explicit {
+            Some(explicit) => std::iter::once(PathWithState::new(
+                explicit.dir.absolute.join(&explicit.name),
+                self.max_symlink_hops,
+            ))
+            .filter(|p| !matches!(p.state, FileState::Missing))
+            .collect(),
+            None => files_on_path(
+                &self.names,
+                &self.path_parts,
+                listings,
+                self.max_symlink_hops,
+            ),
+        }
+    }
+
+    /// Permissions of each found file, empty unless `Which::show_permissions` is enabled
+    fn permissions(&self, found_files: &[PathWithState]) -> HashMap<PathBuf, Permissions> {
+        if !self.show_permissions {
+            return HashMap::new();
+        }
+        found_files
+            .iter()
+            .filter_map(|file| Permissions::of(&file.path).map(|p| (file.path.clone(), p)))
+            .collect()
+    }
+
+    /// The expected name when `found` only matches by ignoring case, on Windows
+    fn case_folded(
+        &self,
+        found: Option<&PathWithState>,
+        explicit: Option<&ExplicitPath>,
+    ) -> Option<OsString> {
+        found
+            .and_then(|p| p.path.file_name())
+            .filter(|found| explicit.is_none() && !self.names.iter().any(|name| name == found))
+            .and_then(|found| {
+                self.names
+                    .iter()
+                    .find(|name| name.eq_ignore_ascii_case(found))
+            })
+            .cloned()
+    }
+
+    /// The directory of `found` when it's not one of `Which::allowed_dirs`
+    fn unapproved_dir(&self, found: Option<&PathWithState>) -> Option<PathBuf> {
+        let allowed = self.allowed_dirs.as_ref()?;
+        found
+            .and_then(|p| p.path.parent())
+            .filter(|dir| !allowed.contains(&normalize(dir)))
+            .map(Path::to_path_buf)
+    }
+
+    /// Shared libraries `found` needs that are missing, empty unless `Which::check_shared_libraries` is enabled on Linux
+    fn missing_shared_libraries(&self, found: Option<&PathWithState>) -> Vec<String> {
+        match found {
+            Some(found) if self.check_shared_libraries && cfg!(target_os = "linux") => {
+                shared_library::missing(Path::new(shared_library::LDD), &found.path)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// A newer copy shadowed by the executable, None unless `Which::check_staleness` is enabled
+    fn newer_shadowed(&self, found_files: &[PathWithState]) -> Option<PathBuf> {
+        if self.check_staleness {
+            newer_shadowed(found_files, STALENESS_THRESHOLD)
+        } else {
+            None
+        }
+    }
+
+    /// The other half of a script wrapper and native pair, None unless `Which::check_script_wrappers` is enabled
+    fn script_wrapper_other(&self, found_files: &[PathWithState]) -> Option<PathBuf> {
+        if self.check_script_wrappers {
+            script_wrapper_other(found_files)
+        } else {
+            None
+        }
+    }
+
+    /// The program given as a path, None when it's a bare name
+    fn explicit(&self) -> Option<ExplicitPath> {
+        self.explicit_dir.as_ref().map(|dir| ExplicitPath {
+            dir: dir.clone(),
+            name: Path::new(&self.program)
+                .file_name()
+                .unwrap_or_default()
+                .to_os_string(),
+            on_path: self
+                .path_parts
+                .iter()
+                .any(|part| part.normalized == dir.normalized),
+        })
+    }
+
+    /// Same as the first of `check().problems()`, but returns as soon as a problem is found
+    ///
+    /// Checks run in the order `Program::problems` sorts them, so nothing that
+    /// can only produce a less severe problem runs after the first hit.
+    /// Suggestions, subcommands, and permissions never add a problem and are skipped.
+    fn first_problem(&self) -> Option<Problem> {
+        let explicit = self.explicit();
+        let listings = match &explicit {
+            Some(_) => &self.explicit_listings,
+            None => &self.listings,
+        };
+        let found_files = self.found_files(explicit.as_ref(), listings);
+        let found = match found_files.iter().find(|p| p.state == FileState::Valid) {
+            Some(found) => found,
+            None => return Some(Problem::NotFound),
+        };
+
+        // Errors
+        let missing_shared_libraries = self.missing_shared_libraries(Some(found));
+        if !missing_shared_libraries.is_empty() {
+            return Some(Problem::MissingSharedLibrary(missing_shared_libraries));
+        }
+        if let Some(dir) = self.unapproved_dir(Some(found)) {
+            return Some(Problem::ResolvedFromUnapprovedDir(dir));
+        }
+        if self.path_unset {
+            return Some(Problem::UnsetPath);
+        } else if self.path_parts.is_empty() {
+            return Some(Problem::EmptyPath);
+        }
+
+        // Warnings
+        if self.quoted_path {
+            return Some(Problem::QuotedPath);
+        }
+        if let Some(file) = found_files.iter().find(|p| p.path != found.path) {
+            return Some(if file.state == FileState::Valid {
+                Problem::Shadowed(file.path.clone())
+            } else {
+                Problem::InvalidFile(file.path.clone(), file.state.clone())
+            });
+        }
+        if let Some(newer) = self.newer_shadowed(&found_files) {
+            return Some(Problem::OlderThanShadowed(found.path.clone(), newer));
+        }
+        if let Some(other) = self.script_wrapper_other(&found_files) {
+            return Some(Problem::ScriptWrapperAndNative(found.path.clone(), other));
+        }
+        for part in &self.path_parts {
+            if part.state != PartState::Valid && !part.expected_empty {
+                return Some(Problem::InvalidPathPart(
+                    part.original.clone(),
+                    part.state.clone(),
+                ));
+            }
+            if part.relative {
+                return Some(Problem::RelativePathPart(part.original.clone()));
+            }
+        }
+        path_part::duplicates(&self.path_parts)
+            .first()
+            .map(|parts| {
+                Problem::DuplicatePathPart(parts.iter().map(|part| part.original.clone()).collect())
+            })
+    }
+
     fn check(&self) -> Program {
+        let explicit = self.explicit();
+        // An explicit program is only looked for in its own directory
+        let (name, listings) = match &explicit {
+            Some(explicit) => (&explicit.name, &self.explicit_listings),
+            None => (&self.program, &self.listings),
+        };
+        let found_files = self.found_files(explicit.as_ref(), listings);
+        let found = found_files.iter().find(|p| p.state == FileState::Valid);
+        let case_folded = self.case_folded(found, explicit.as_ref());
+        // Alternatives are only useful when the program isn't found
+        let alternatives = found.is_none();
+        let subcommand_separator = self.subcommand_separator.as_ref().filter(|_| alternatives);
+        let unapproved_dir = self.unapproved_dir(found);
+        let missing_shared_libraries = self.missing_shared_libraries(found);
+        let newer_shadowed = self.newer_shadowed(&found_files);
+        let script_wrapper_other = self.script_wrapper_other(&found_files);
+
+        let guess_limit = self.guess_limit;
+        let (suggested_paths, suggestion_debug) = suggest::spelling(
+            name,
+            listings,
+            guess_limit,
+            self.suggest_total_file_limit,
+            self.suggest_executables_only,
+            self.suggest_debug,
+        );
+
         Program {
             name: self.program.clone(),
-            suggested: suggest::spelling(&self.program, &self.path_parts, self.guess_limit),
+            suggested: suggested_paths.as_deref().map(suggest::file_names),
+            suggested_paths: suggested_paths.unwrap_or_default(),
+            suggestion_debug,
+            suggest_truncated_at: suggest::truncated_at(listings, self.suggest_total_file_limit)
+                .filter(|_| guess_limit > 0),
+            guess_limit,
+            suggest_total_file_limit: self.suggest_total_file_limit,
+            suggest_executables_only: self.suggest_executables_only,
+            separator_variants: if alternatives {
+                suggest::separator_variants(name, listings)
+            } else {
+                Vec::new()
+            },
+            prefixed: if alternatives {
+                suggest::prefixed(name, listings)
+            } else {
+                Vec::new()
+            },
+            local_bin: if alternatives && explicit.is_none() {
+                local_bin(&self.cwd, &self.local_bin_dirs, name)
+            } else {
+                None
+            },
+            subcommands: subcommand_separator
+                .map(|separator| suggest::subcommands(name, listings, separator))
+                .unwrap_or_default(),
+            path_unset: self.path_unset,
             path_parts: self.path_parts.clone(),
-            found_files: files_on_path(&self.program, &self.path_parts),
+            permissions: self.permissions(&found_files),
+            found_files,
+            unapproved_dir,
+            missing_shared_libraries,
+            newer_shadowed,
+            script_wrapper_other,
+            explicit,
+            case_folded,
+            quoted_path: self.quoted_path,
+            unquoted_path: self.quoted_path && self.unquote_path,
+            process_path_diff: self.process_path_diff.clone(),
+            sudo: None,
         }
     }
 }
 
-fn files_on_path(name: &OsString, path_parts: &[PathPart]) -> Vec<PathWithState> {
+/// Windows is case-insensitive, but case-preserving
+const FOLD_CASE: bool = cfg!(windows);
+
+/// The file names to look for in each directory, the program followed by the program with each extension
+///
+/// When folding case extensions are lowercased, the way they are usually written on disk.
+fn variants(program: &OsStr, pathext: Option<&OsStr>, fold_case: bool) -> Vec<OsString> {
+    let extensions = pathext
+        .map(OsStr::to_string_lossy)
+        .unwrap_or_default()
+        .split(';')
+        .filter(|ext| !ext.is_empty())
+        .map(|ext| {
+            if fold_case {
+                ext.to_lowercase()
+            } else {
+                ext.to_string()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    std::iter::once(program.to_os_string())
+        .chain(extensions.into_iter().map(|ext| {
+            let mut name = program.to_os_string();
+            name.push(ext);
+            name
+        }))
+        .unique()
+        .collect()
+}
+
+/// The on-disk name of `name` in `listing`, matched ignoring case when `FOLD_CASE` is set
+fn entry_named<'a>(listing: &'a Listing, name: &OsStr) -> Option<&'a OsString> {
+    listing.names.iter().find(|entry| {
+        if FOLD_CASE {
+            entry.eq_ignore_ascii_case(name)
+        } else {
+            entry.as_os_str() == name
+        }
+    })
+}
+
+fn files_on_path(
+    names: &[OsString],
+    path_parts: &[PathPart],
+    listings: &[Listing],
+    max_symlink_hops: Option<usize>,
+) -> Vec<PathWithState> {
     path_parts
         .iter()
-        .map(|p| p.absolute.join(name))
-        .map(PathWithState::new)
+        .flat_map(|p| {
+            let listing = listings.iter().find(|listing| listing.dir == p.absolute);
+            names
+                .iter()
+                // Skip checking the disk when the directory is known not to contain the name
+                .filter_map(move |name| match listing {
+                    Some(listing) => entry_named(listing, name),
+                    None => Some(name),
+                })
+                .map(|name| p.absolute.join(name))
+        })
+        .map(|path| PathWithState::new(path, max_symlink_hops))
         .filter(|p| !matches!(p.state, FileState::Missing))
         .collect()
 }
+
+/// The nearest valid executable in a project local bin directory, checked from `cwd` up to the root
+fn local_bin(cwd: &Path, local_bin_dirs: &[PathBuf], name: &OsString) -> Option<PathBuf> {
+    cwd.ancestors()
+        .flat_map(|dir| {
+            local_bin_dirs
+                .iter()
+                .map(move |bin| dir.join(bin).join(name))
+        })
+        .find(|path| file_state(path) == FileState::Valid)
+}
+
+/// The first valid native executable after a winning script wrapper, or the first
+/// valid script wrapper after a winning native executable
+fn script_wrapper_other(found_files: &[PathWithState]) -> Option<PathBuf> {
+    // Some(true) for a script wrapper, Some(false) for a native executable
+    let is_script = |path: &Path| {
+        let ext = path.extension()?.to_string_lossy().to_lowercase();
+        match ext.as_str() {
+            "cmd" | "bat" => Some(true),
+            "exe" => Some(false),
+            _ => None,
+        }
+    };
+    let mut valid = found_files
+        .iter()
+        .filter(|p| p.state == FileState::Valid)
+        .map(|p| &p.path);
+    let winner_is_script = is_script(valid.next()?)?;
+
+    valid
+        .find(|path| is_script(path) == Some(!winner_is_script))
+        .cloned()
+}
+
+/// The newest valid executable after the winner, if the winner is older than it by more than `threshold`
+fn newer_shadowed(found_files: &[PathWithState], threshold: Duration) -> Option<PathBuf> {
+    let modified = |file: &PathWithState| {
+        std::fs::metadata(&file.path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    };
+    let mut valid = found_files.iter().filter(|p| p.state == FileState::Valid);
+    let winner = modified(valid.next()?)?;
+
+    valid
+        .filter_map(|file| modified(file).map(|time| (time, file)))
+        .filter(|(time, _)| match time.duration_since(winner) {
+            Ok(newer_by) => newer_by > threshold,
+            Err(_) => false,
+        })
+        .max_by_key(|(time, _)| *time)
+        .map(|(_, file)| file.path.clone())
+}
+
+/// The inside of a PATH entirely wrapped in matching single or double quotes
+///
+/// A PATH set with `export PATH='"/usr/bin:/bin"'` holds the quotes literally.
+fn unquoted(path_env: &OsStr) -> Option<OsString> {
+    let path_env = path_env.to_str()?;
+    ['"', '\'']
+        .into_iter()
+        .find_map(|quote| {
+            path_env
+                .strip_prefix(quote)
+                .and_then(|rest| rest.strip_suffix(quote))
+        })
+        .map(OsString::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn which_first_of_many() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let tmp_dir_two = tempfile::tempdir().unwrap();
+        for dir in [tmp_dir.path(), tmp_dir_two.path()] {
+            let file = dir.join("lol");
+            std::fs::write(&file, "contents").unwrap();
+            std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let which = Which {
+            program: OsString::from("lol"),
+            path_env: Some(
+                [tmp_dir.path().as_os_str(), tmp_dir_two.path().as_os_str()]
+                    .join(&OsString::from(":")),
+            ),
+            ..Which::default()
+        };
+        assert_eq!(Some(tmp_dir.path().join("lol")), which.which().unwrap());
+    }
+
+    #[test]
+    fn which_not_found() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let which = Which {
+            program: OsString::from("lol"),
+            path_env: Some(tmp_dir.path().as_os_str().into()),
+            ..Which::default()
+        };
+        assert_eq!(None, which.which().unwrap());
+    }
+
+    #[test]
+    fn from_conversions_only_set_program() {
+        let expected = Which {
+            program: OsString::from("bundle"),
+            ..Which::default()
+        };
+
+        assert_eq!(expected, Which::from("bundle"));
+        assert_eq!(expected, Which::from(String::from("bundle")));
+        assert_eq!(expected, Which::from(OsStr::new("bundle")));
+        assert_eq!(
+            vec![expected],
+            ["bundle"].into_iter().map(Which::from).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn check_unquoted() {
+        assert_eq!(
+            Some(OsString::from("/usr/bin:/bin")),
+            unquoted(OsStr::new(r#""/usr/bin:/bin""#))
+        );
+        assert_eq!(
+            Some(OsString::from("/usr/bin:/bin")),
+            unquoted(OsStr::new("'/usr/bin:/bin'"))
+        );
+        assert_eq!(None, unquoted(OsStr::new(r#""/usr/bin:/bin'"#)));
+        assert_eq!(None, unquoted(OsStr::new(r#""/usr/bin"#)));
+        assert_eq!(None, unquoted(OsStr::new("/usr/bin:/bin")));
+        assert_eq!(None, unquoted(OsStr::new(r#"""#)));
+    }
+
+    #[test]
+    fn validate_default() {
+        assert_eq!(Ok(()), Which::new("bundle").validate());
+        assert_eq!(Ok(()), Which::default().validate());
+    }
+
+    #[test]
+    fn validate_cwd_not_dir() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let file = tmp_dir.path().join("file");
+        std::fs::write(&file, "contents").unwrap();
+
+        let which = Which {
+            cwd: Some(file.clone()),
+            ..Which::new("bundle")
+        };
+        assert_eq!(
+            Err(vec![WhichConfigWarning::CwdNotDir(file)]),
+            which.validate()
+        );
+    }
+
+    #[test]
+    fn validate_guess_limit() {
+        let which = Which {
+            guess_limit: 10_000,
+            ..Which::new("bundle")
+        };
+        assert_eq!(
+            Err(vec![WhichConfigWarning::GuessLimitTooLarge(10_000)]),
+            which.validate()
+        );
+    }
+
+    #[test]
+    fn validate_blank_program() {
+        let which = Which {
+            guess_limit: 5,
+            ..Which::default()
+        };
+        assert_eq!(
+            Err(vec![WhichConfigWarning::BlankProgram]),
+            which.validate()
+        );
+    }
+
+    #[test]
+    fn validate_empty_subcommand_separator() {
+        let which = Which {
+            subcommand_separator: Some(String::new()),
+            ..Which::new("bundle")
+        };
+        assert_eq!(
+            Err(vec![WhichConfigWarning::EmptySubcommandSeparator]),
+            which.validate()
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn check_uppercase_extension_folds_case() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let file = dir.join("tool.EXE");
+        std::fs::write(&file, "contents").unwrap();
+
+        let which = Which {
+            path_env: Some(dir.as_os_str().to_os_string()),
+            pathext: Some(OsString::from(".COM;.EXE")),
+            ..Which::new("tool")
+        };
+        // The filesystem resolves any case, the path is as searched
+        assert_eq!(Some(dir.join("tool.exe")), which.which().unwrap());
+
+        let program = which.diagnose().unwrap();
+        assert_eq!(Some(&file), program.executable().map(|p| &p.path));
+        assert_eq!(Some(OsString::from("tool.exe")), program.case_folded);
+        assert!(format!("{program}")
+            .contains(r#"Info: The file name only matches "tool.exe" when ignoring case"#));
+    }
+
+    #[test]
+    fn program_variants_with_extensions() {
+        let which = Which {
+            pathext: Some(OsString::from(".sh;;.sh")),
+            ..Which::new("tool")
+        };
+        assert_eq!(
+            vec![OsString::from("tool"), OsString::from("tool.sh")],
+            which.program_variants()
+        );
+
+        let which = Which {
+            pathext: None,
+            ..Which::new("tool")
+        };
+        assert_eq!(vec![OsString::from("tool")], which.program_variants());
+    }
+
+    #[test]
+    fn variants_fold_pathext_case() {
+        assert_eq!(
+            vec![
+                OsString::from("tool"),
+                OsString::from("tool.exe"),
+                OsString::from("tool.cmd")
+            ],
+            variants(OsStr::new("tool"), Some(OsStr::new(".EXE;.CMD;.exe")), true)
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn program_variants_windows_pathext() {
+        let which = Which {
+            pathext: Some(OsString::from(".EXE;.CMD")),
+            ..Which::new("tool")
+        };
+        assert_eq!(
+            vec![
+                OsString::from("tool"),
+                OsString::from("tool.exe"),
+                OsString::from("tool.cmd")
+            ],
+            which.program_variants()
+        );
+    }
+
+    #[test]
+    fn script_wrapper_other_finds_native() {
+        let file = |path: &str, state: FileState| PathWithState {
+            path: PathBuf::from(path),
+            state,
+        };
+
+        assert_eq!(
+            Some(PathBuf::from("/b/tool.EXE")),
+            script_wrapper_other(&[
+                file("/a/tool.cmd", FileState::Valid),
+                file("/a/tool.exe", FileState::NotExecutable),
+                file("/b/tool.EXE", FileState::Valid),
+            ])
+        );
+        assert_eq!(
+            None,
+            script_wrapper_other(&[
+                file("/a/tool.bat", FileState::Valid),
+                file("/a/tool.cmd", FileState::Valid),
+            ])
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn check_script_wrapper_and_native() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let exe = dir.join("tool.exe");
+        let cmd = dir.join("tool.cmd");
+        std::fs::write(&exe, "contents").unwrap();
+        std::fs::write(&cmd, "@echo off").unwrap();
+
+        let program = Which {
+            path_env: Some(dir.as_os_str().to_os_string()),
+            pathext: Some(OsString::from(".CMD;.EXE")),
+            check_script_wrappers: true,
+            ..Which::new("tool")
+        }
+        .diagnose()
+        .unwrap();
+
+        assert_eq!(Some(&cmd), program.executable().map(|p| &p.path));
+        assert!(program
+            .problems()
+            .contains(&Problem::ScriptWrapperAndNative(cmd.clone(), exe.clone())));
+        assert!(format!("{program}").contains("since its extension comes first in PATHEXT"));
+    }
+}